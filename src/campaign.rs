@@ -0,0 +1,354 @@
+//! Campaign
+//!
+//! Ordered levels described in `assets/campaign.ron`, each one with its own
+//! maze and mechanics. A level is unlocked once the previous one has been
+//! finished, and gets up to three stars depending on the time it took
+//! compared to its par time. Reaching the goal starts the next level.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::GoalReached,
+    options::{GameOption, GameOptions},
+    resources::{Algorithm, BumperConfig, Difficulty, GameMode, MazeConfig},
+    storage, AppState,
+};
+
+const CAMPAIGN: &str = include_str!("../assets/campaign.ron");
+const SAVE_KEY: &str = "campaign";
+const TEXT_COLOR: Color = Color::rgb(0.1, 0.1, 0.1);
+const NORMAL_BUTTON: Color = Color::rgb(0.3, 0.3, 0.3);
+const LOCKED_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+
+#[derive(Deserialize)]
+struct Level {
+    name: String,
+    width: i32,
+    height: i32,
+    algorithm: Algorithm,
+    seed: u64,
+    #[serde(default)]
+    mechanics: Vec<GameOption>,
+    /// Speed of the moving obstacles, when they are enabled.
+    #[serde(default)]
+    obstacle_difficulty: Difficulty,
+    /// Number of bumpers, when they are enabled, the default one if unset.
+    #[serde(default)]
+    bumpers: Option<usize>,
+    /// Speed change given by the bumpers, the default one if unset.
+    #[serde(default)]
+    bumper_strength: Option<f32>,
+    /// Time, in seconds, to beat to get three stars.
+    par_time: f32,
+}
+
+impl Level {
+    /// Stars earned when the goal is reached after `time` seconds.
+    fn stars(&self, time: f32) -> u8 {
+        if time <= self.par_time {
+            3
+        } else if time <= self.par_time * 1.5 {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Campaign(Vec<Level>);
+
+/// Saved progress of the player.
+#[derive(Resource, Default, Serialize, Deserialize)]
+struct CampaignProgress {
+    /// Best stars of each level, `0` when it hasn't been finished yet.
+    stars: Vec<u8>,
+}
+
+impl CampaignProgress {
+    fn stars(&self, level: usize) -> u8 {
+        self.stars.get(level).copied().unwrap_or_default()
+    }
+
+    fn is_unlocked(&self, level: usize) -> bool {
+        level == 0 || self.stars(level - 1) > 0
+    }
+
+    /// Keep `stars` if it is better than the previous ones, return whether
+    /// it was.
+    fn record(&mut self, level: usize, stars: u8) -> bool {
+        if stars <= self.stars(level) {
+            return false;
+        }
+        if self.stars.len() <= level {
+            self.stars.resize(level + 1, 0);
+        }
+        self.stars[level] = stars;
+        true
+    }
+}
+
+/// Everything needed to set up a level.
+#[derive(SystemParam)]
+struct LevelLoader<'w> {
+    campaign: Res<'w, Campaign>,
+    maze_config: ResMut<'w, MazeConfig>,
+    options: GameOptions<'w>,
+    mode: ResMut<'w, GameMode>,
+}
+
+impl LevelLoader<'_> {
+    /// Configure the game for the level `index`.
+    fn load(&mut self, index: usize) {
+        let level = &self.campaign.0[index];
+        info!("Level {} : {}", index + 1, level.name);
+        self.maze_config.set_algorithm(level.algorithm);
+        self.maze_config.set_width(level.width);
+        self.maze_config.set_height(level.height);
+        self.maze_config.set_seed(Some(level.seed));
+        self.options.keep_user_options();
+        for option in GameOption::ALL {
+            self.options.set(option, level.mechanics.contains(&option));
+        }
+        self.options.set_difficulty(level.obstacle_difficulty);
+        let bumpers = BumperConfig::default();
+        self.options.set_bumpers(
+            level.bumpers.unwrap_or(bumpers.bumpers),
+            level.bumper_strength.unwrap_or(bumpers.bumper_kick),
+        );
+        *self.mode = GameMode::Campaign(index);
+    }
+}
+
+#[derive(Component)]
+struct LevelSelectComponent;
+
+#[derive(Component, Clone, Copy)]
+enum LevelSelectAction {
+    Play(usize),
+    Back,
+}
+
+pub(crate) struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Campaign(
+            ron::from_str(CAMPAIGN).expect("Invalid campaign file"),
+        ))
+        .insert_resource(storage::load::<CampaignProgress>(SAVE_KEY).unwrap_or_default())
+        .add_systems(OnEnter(AppState::LevelSelect), setup)
+        .add_systems(
+            Update,
+            handle_buttons.run_if(in_state(AppState::LevelSelect)),
+        )
+        .add_systems(Update, finish_level.run_if(in_state(AppState::Game)))
+        .add_systems(
+            OnExit(AppState::LevelSelect),
+            remove::<LevelSelectComponent>,
+        );
+    }
+}
+
+fn setup(mut commands: Commands, campaign: Res<Campaign>, progress: Res<CampaignProgress>) {
+    let button_style = Style {
+        width: Val::Px(220.0),
+        height: Val::Px(80.0),
+        margin: UiRect::all(Val::Px(10.0)),
+        flex_direction: FlexDirection::Column,
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let text_style = TextStyle {
+        font_size: 30.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    commands
+        .spawn(Camera2dBundle::default())
+        .insert(LevelSelectComponent);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            LevelSelectComponent,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "Campaign",
+                    TextStyle {
+                        font_size: 80.0,
+                        color: TEXT_COLOR,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(30.0)),
+                    ..default()
+                }),
+            );
+
+            // Levels
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(960.0),
+                        flex_wrap: FlexWrap::Wrap,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (index, level) in campaign.0.iter().enumerate() {
+                        let unlocked = progress.is_unlocked(index);
+                        let status = if unlocked {
+                            format!("Stars : {}/3", progress.stars(index))
+                        } else {
+                            "Locked".to_string()
+                        };
+                        let mut button = parent.spawn(ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: if unlocked {
+                                NORMAL_BUTTON.into()
+                            } else {
+                                LOCKED_BUTTON.into()
+                            },
+                            ..default()
+                        });
+                        // Locked levels have no action
+                        if unlocked {
+                            button.insert(LevelSelectAction::Play(index));
+                        }
+                        button.with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                format!("{}. {}", index + 1, level.name),
+                                text_style.clone(),
+                            ));
+                            parent.spawn(TextBundle::from_section(status, text_style.clone()));
+                        });
+                    }
+                });
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(250.0),
+                            height: Val::Px(65.0),
+                            margin: UiRect::all(Val::Px(30.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: NORMAL_BUTTON.into(),
+                        ..default()
+                    },
+                    LevelSelectAction::Back,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Back",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: TEXT_COLOR,
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+#[allow(clippy::type_complexity)]
+fn handle_buttons(
+    interaction_query: Query<
+        (&Interaction, &LevelSelectAction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut loader: LevelLoader,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            match action {
+                LevelSelectAction::Play(index) => {
+                    loader.load(*index);
+                    state.set(AppState::Game);
+                }
+                LevelSelectAction::Back => state.set(AppState::Menu),
+            }
+        }
+    }
+}
+
+/// Record the stars of the level and go to the next one.
+fn finish_level(
+    mut reached: EventReader<GoalReached>,
+    mut progress: ResMut<CampaignProgress>,
+    mut loader: LevelLoader,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    for goal in reached.iter() {
+        let GameMode::Campaign(index) = *loader.mode else {
+            continue;
+        };
+        let stars = loader.campaign.0[index].stars(goal.time);
+        info!("Level {} finished with {stars} stars", index + 1);
+        if progress.record(index, stars) {
+            storage::save(SAVE_KEY, &*progress);
+        }
+
+        if index + 1 < loader.campaign.0.len() {
+            loader.load(index + 1);
+            state.set(AppState::Loading);
+        } else {
+            info!("Campaign finished");
+            state.set(AppState::Menu);
+        }
+    }
+}
+
+fn remove<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
+    for entity in &to_despawn {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_keeps_the_best_stars() {
+        let mut progress = CampaignProgress::default();
+        assert!(progress.record(2, 1));
+        assert_eq!(progress.stars, vec![0, 0, 1]);
+        assert!(progress.record(2, 3));
+        assert!(!progress.record(2, 2));
+        assert_eq!(progress.stars(2), 3);
+        assert_eq!(progress.stars(5), 0);
+    }
+
+    #[test]
+    fn levels_unlock_in_order() {
+        let mut progress = CampaignProgress::default();
+        assert!(progress.is_unlocked(0));
+        assert!(!progress.is_unlocked(1));
+        progress.record(0, 1);
+        assert!(progress.is_unlocked(1));
+        assert!(!progress.is_unlocked(2));
+    }
+}
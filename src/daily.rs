@@ -0,0 +1,265 @@
+//! Daily challenge
+//!
+//! One maze a day, derived from the date only, so every player gets the same
+//! one on every platform. Results are kept in a local leaderboard for each
+//! day, shown at the end of the run with the number of days in a row the
+//! challenge was completed.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::GoalReached,
+    options::{GameOption, GameOptions},
+    resources::{Algorithm, GameMode, MazeConfig},
+    results::Results,
+    storage, AppState,
+};
+
+const SAVE_KEY: &str = "daily";
+const DAILY_SALT: u64 = 0x6461696c79;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+const MIN_SIZE: i32 = 8;
+const MAX_SIZE: i32 = 16;
+const ALGORITHMS: [Algorithm; 4] = [
+    Algorithm::Ellers,
+    Algorithm::GrowingTree,
+    Algorithm::Prims,
+    Algorithm::RecursiveBacktracking,
+];
+/// Number of results kept in the leaderboard of a day.
+const LEADERBOARD_SIZE: usize = 10;
+/// Number of days the leaderboards are kept.
+const DAYS_KEPT: u64 = 30;
+
+/// Days since the Unix epoch, in UTC so it doesn't depend on the time zone.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn today() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or_default()
+}
+
+/// Days since the Unix epoch, in UTC so it doesn't depend on the time zone.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn today() -> u64 {
+    // `SystemTime` isn't available in the browser
+    (js_sys::Date::now() / 1000.) as u64 / SECONDS_PER_DAY
+}
+
+/// Maze of the challenge of `day`, and its mechanic, if any.
+fn challenge(day: u64) -> (MazeConfig, Option<GameOption>) {
+    // Only draw fixed size integers : `usize` ranges don't give the same
+    // values on 32 bits (wasm) and 64 bits platforms
+    let mut rng = ChaCha8Rng::seed_from_u64(day ^ DAILY_SALT);
+    let size = rng.gen_range(MIN_SIZE, MAX_SIZE + 1);
+    let algorithm = ALGORITHMS[(rng.gen::<u32>() % ALGORITHMS.len() as u32) as usize];
+    // At most one mechanic, none one day out of GameOption::ALL.len() + 1
+    let mechanic = GameOption::ALL
+        .get((rng.gen::<u32>() % (GameOption::ALL.len() as u32 + 1)) as usize)
+        .copied();
+
+    let mut maze_config = MazeConfig::default();
+    maze_config.set_algorithm(algorithm);
+    maze_config.set_width(size);
+    maze_config.set_height(size);
+    maze_config.set_seed(Some(rng.gen()));
+    (maze_config, mechanic)
+}
+
+/// Configure the game for the challenge of `day`.
+pub(crate) fn load(
+    day: u64,
+    mode: &mut GameMode,
+    maze_config: &mut MazeConfig,
+    options: &mut GameOptions,
+) {
+    info!("Daily challenge of day {day}");
+    let (config, mechanic) = challenge(day);
+    *maze_config = config;
+    options.keep_user_options();
+    for option in GameOption::ALL {
+        options.set(option, Some(option) == mechanic);
+    }
+    *mode = GameMode::Daily(day);
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct DailyResult {
+    score: u32,
+    time: f32,
+}
+
+/// Saved results of the player.
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub(crate) struct DailyRecords {
+    /// Best results of the last days, best score first.
+    #[serde(default)]
+    leaderboards: BTreeMap<u64, Vec<DailyResult>>,
+    /// Last day the challenge was completed.
+    last_day: Option<u64>,
+    streak: u32,
+    best_streak: u32,
+}
+
+impl DailyRecords {
+    /// Days in a row the challenge was completed, up to today.
+    pub(crate) fn streak(&self, today: u64) -> u32 {
+        match self.last_day {
+            Some(day) if day + 1 >= today => self.streak,
+            _ => 0,
+        }
+    }
+
+    /// Best results of `day`, best score first.
+    fn leaderboard(&self, day: u64) -> &[DailyResult] {
+        self.leaderboards
+            .get(&day)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Best score of `day`, if the challenge was completed.
+    pub(crate) fn best(&self, day: u64) -> Option<u32> {
+        self.leaderboard(day).first().map(|result| result.score)
+    }
+
+    /// Add a result of `day`, return its rank in the leaderboard of the day,
+    /// if it's in.
+    fn record(&mut self, day: u64, result: DailyResult) -> Option<usize> {
+        match self.last_day {
+            Some(last_day) if last_day == day => (),
+            Some(last_day) if last_day + 1 == day => self.streak += 1,
+            _ => self.streak = 1,
+        }
+        self.last_day = Some(day);
+        self.best_streak = self.best_streak.max(self.streak);
+
+        // Older challenges can't be played anymore
+        self.leaderboards
+            .retain(|other_day, _| *other_day + DAYS_KEPT > day);
+        let leaderboard = self.leaderboards.entry(day).or_default();
+        let rank = leaderboard
+            .iter()
+            .position(|other| other.score < result.score)
+            .unwrap_or(leaderboard.len());
+        leaderboard.insert(rank, result);
+        leaderboard.truncate(LEADERBOARD_SIZE);
+        (rank < LEADERBOARD_SIZE).then_some(rank)
+    }
+}
+
+pub(crate) struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<DailyRecords>(SAVE_KEY).unwrap_or_default())
+            .add_systems(Update, finish_daily.run_if(in_state(AppState::Game)));
+    }
+}
+
+fn finish_daily(
+    mut commands: Commands,
+    mut reached: EventReader<GoalReached>,
+    mode: Res<GameMode>,
+    mut records: ResMut<DailyRecords>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    for goal in reached.iter() {
+        let GameMode::Daily(day) = *mode else {
+            continue;
+        };
+        let rank = records.record(
+            day,
+            DailyResult {
+                score: goal.score,
+                time: goal.time,
+            },
+        );
+        storage::save(SAVE_KEY, &*records);
+
+        let mut lines = vec![match rank {
+            Some(rank) => format!("Rank {} of the day", rank + 1),
+            None => "Not in the leaderboard of the day".to_string(),
+        }];
+        lines.push(format!(
+            "Streak : {} days (best : {} days)",
+            records.streak, records.best_streak
+        ));
+        lines.extend(
+            records
+                .leaderboard(day)
+                .iter()
+                .enumerate()
+                .map(|(index, result)| {
+                    format!("{}. {} in {:.2}s", index + 1, result.score, result.time)
+                }),
+        );
+        commands.insert_resource(Results::new("Daily challenge", lines));
+        state.set(AppState::Results);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(score: u32) -> DailyResult {
+        DailyResult { score, time: 10. }
+    }
+
+    #[test]
+    fn same_day_same_challenge() {
+        for day in 19_000..19_010 {
+            assert!(challenge(day) == challenge(day));
+        }
+        assert!(challenge(19_000) != challenge(19_001));
+    }
+
+    #[test]
+    fn streak_counts_days_in_a_row() {
+        let mut records = DailyRecords::default();
+        assert_eq!(records.streak(10), 0);
+        records.record(10, result(5));
+        records.record(10, result(6));
+        assert_eq!(records.streak(10), 1);
+        records.record(11, result(5));
+        assert_eq!(records.streak(11), 2);
+        // Still running until the end of the next day
+        assert_eq!(records.streak(12), 2);
+        assert_eq!(records.streak(13), 0);
+        records.record(13, result(5));
+        assert_eq!(records.streak(13), 1);
+        assert_eq!(records.best_streak, 2);
+    }
+
+    #[test]
+    fn leaderboard_of_each_day() {
+        let mut records = DailyRecords::default();
+        assert_eq!(records.record(10, result(5)), Some(0));
+        assert_eq!(records.record(10, result(8)), Some(0));
+        assert_eq!(records.record(10, result(6)), Some(1));
+        assert_eq!(records.record(11, result(2)), Some(0));
+        assert_eq!(records.best(10), Some(8));
+        assert_eq!(records.best(11), Some(2));
+        assert_eq!(records.best(12), None);
+        for score in 0..LEADERBOARD_SIZE as u32 {
+            records.record(11, result(10 + score));
+        }
+        assert_eq!(records.record(11, result(1)), None);
+        assert_eq!(records.leaderboard(11).len(), LEADERBOARD_SIZE);
+    }
+
+    #[test]
+    fn old_leaderboards_are_dropped() {
+        let mut records = DailyRecords::default();
+        records.record(10, result(5));
+        records.record(10 + DAYS_KEPT, result(5));
+        assert_eq!(records.best(10), None);
+    }
+}
@@ -0,0 +1,119 @@
+//! Player actions other than tilting the board
+//!
+//! The player can either restart the current maze (the marble goes back
+//! to the start cell), ask for a new maze with the same settings, ask
+//! for a hint, hide the ghost or go back to the menu. Modes with a seeded maze (daily
+//! challenge, campaign, replay...) have no new maze.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{resources::MazeConfig, AppState};
+
+use super::{
+    board::{place_marble, Floor, Marble, Player, Step},
+    hud::{GameTimer, RunStats},
+};
+
+/// An action triggered by a key, a gamepad button or a HUD button.
+#[derive(Event, Component, Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum GameAction {
+    /// Put the marble back on the start cell and level the floor,
+    /// keeping the same maze.
+    Restart,
+    /// Generate a new maze using the same settings.
+    NewMaze,
+    /// Show the way to the goal, see [super::hint].
+    Hint,
+    /// Stop racing the ghost, see [super::ghost].
+    HideGhost,
+    /// Leave the game and go back to the menu.
+    Quit,
+}
+
+pub(crate) fn handle_action_input(
+    keyboard: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    mut actions: EventWriter<GameAction>,
+) {
+    if keyboard.just_pressed(KeyCode::R) {
+        actions.send(GameAction::Restart);
+    }
+    if keyboard.just_pressed(KeyCode::N) {
+        actions.send(GameAction::NewMaze);
+    }
+    if keyboard.just_pressed(KeyCode::H) {
+        actions.send(GameAction::Hint);
+    }
+    if keyboard.just_pressed(KeyCode::G) {
+        actions.send(GameAction::HideGhost);
+    }
+    if keyboard.just_pressed(KeyCode::Escape) {
+        actions.send(GameAction::Quit);
+    }
+
+    for gamepad in gamepads.iter() {
+        if buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start)) {
+            actions.send(GameAction::Restart);
+        }
+        if buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Select)) {
+            actions.send(GameAction::NewMaze);
+        }
+        if buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::North)) {
+            actions.send(GameAction::Hint);
+        }
+        if buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::West)) {
+            actions.send(GameAction::HideGhost);
+        }
+        if buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::East)) {
+            actions.send(GameAction::Quit);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handle_actions(
+    mut actions: EventReader<GameAction>,
+    maze_config: Res<MazeConfig>,
+    mut floors: Query<(&mut Transform, Option<&Player>), (With<Floor>, Without<Marble>)>,
+    mut marbles: Query<(&Marble, &mut Transform, &mut Velocity, Option<&Player>), Without<Floor>>,
+    mut timer: ResMut<GameTimer>,
+    mut stats: ResMut<RunStats>,
+    mut step: ResMut<Step>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    for action in actions.iter() {
+        match action {
+            GameAction::Restart => {
+                info!("Restart maze");
+                for (marble, mut transform, mut velocity, player) in &mut marbles {
+                    if let Some((mut floor, _)) =
+                        floors.iter_mut().find(|(_, owner)| *owner == player)
+                    {
+                        place_marble(&mut floor, &mut transform, &mut velocity, marble.start);
+                    }
+                }
+                timer.reset();
+                *stats = RunStats::default();
+                // A new run starts, see super::replay
+                step.0 = 0;
+            }
+            // The same maze would be generated again
+            GameAction::NewMaze if maze_config.is_seeded() => {
+                info!("No new maze in this mode");
+            }
+            GameAction::NewMaze => {
+                info!("Generate a new maze");
+                // Going through the loading state runs the usual OnExit/OnEnter
+                // systems, so everything is despawned and spawned again.
+                state.set(AppState::Loading);
+            }
+            GameAction::Hint | GameAction::HideGhost => (),
+            GameAction::Quit => {
+                info!("Back to the menu");
+                state.set(AppState::Menu);
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Attract mode
+//!
+//! Behind the menu, the camera slowly turns around a small maze solved by
+//! the [super::autopilot]. A new maze is generated once the marble reached
+//! the goal, or after a while if it is stuck. Everything is removed with the
+//! menu.
+
+use std::f32::consts::TAU;
+
+use bevy::{ecs::schedule::SystemConfigs, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    menu::MenuComponent,
+    resources::{Algorithm, CurrentMaze, MazeConfig},
+};
+
+use super::{
+    autopilot,
+    board::{self, Floor, Marble, TiltInput},
+    GameComponent,
+};
+
+const SIZE: i32 = 8;
+const ALGORITHMS: [Algorithm; 4] = [
+    Algorithm::GrowingTree,
+    Algorithm::RecursiveBacktracking,
+    Algorithm::Prims,
+    Algorithm::Ellers,
+];
+/// Seconds before switching to another maze.
+const DURATION: f32 = 40.;
+/// Seconds for the camera to go around the board.
+const ORBIT_PERIOD: f32 = 60.;
+const CAMERA_DISTANCE: f32 = 6.;
+const CAMERA_HEIGHT: f32 = 5.;
+
+#[derive(Resource)]
+pub(crate) struct AttractMaze {
+    /// Number of mazes shown so far.
+    count: usize,
+    timer: Timer,
+    renew: bool,
+}
+
+impl Default for AttractMaze {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            timer: Timer::from_seconds(DURATION, TimerMode::Once),
+            renew: false,
+        }
+    }
+}
+
+/// Elements of the current attract maze, on top of [MenuComponent].
+#[derive(Component)]
+pub(crate) struct AttractComponent;
+
+#[derive(Component)]
+pub(crate) struct AttractCamera;
+
+/// Systems replacing the attract maze by a new one.
+pub(crate) fn spawn_maze() -> SystemConfigs {
+    (
+        new_maze,
+        apply_deferred,
+        board::setup_board,
+        apply_deferred,
+        adopt_board,
+    )
+        .chain()
+}
+
+pub(crate) fn setup_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(CAMERA_DISTANCE, CAMERA_HEIGHT, 0.)
+                .looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        AttractCamera,
+        MenuComponent,
+    ));
+}
+
+fn new_maze(
+    mut commands: Commands,
+    mut attract: ResMut<AttractMaze>,
+    previous: Query<Entity, With<AttractComponent>>,
+) {
+    for entity in &previous {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let mut config = MazeConfig::default();
+    config.set_width(SIZE);
+    config.set_height(SIZE);
+    config.set_algorithm(ALGORITHMS[attract.count % ALGORITHMS.len()]);
+    commands.insert_resource(board::new_maze(&config));
+
+    attract.count += 1;
+    attract.timer.reset();
+    attract.renew = false;
+}
+
+/// Make the board spawned for the game part of the menu.
+#[allow(clippy::type_complexity)]
+fn adopt_board(
+    mut commands: Commands,
+    board: Query<
+        Entity,
+        (
+            With<GameComponent>,
+            Without<Parent>,
+            Without<AttractComponent>,
+        ),
+    >,
+) {
+    for entity in &board {
+        commands
+            .entity(entity)
+            .insert((AttractComponent, MenuComponent));
+    }
+}
+
+/// Ask for a new maze once the goal is reached or the time is over.
+pub(crate) fn watch_maze(
+    time: Res<Time>,
+    mut attract: ResMut<AttractMaze>,
+    current_maze: Option<Res<CurrentMaze>>,
+    floor: Query<&Transform, (With<Floor>, Without<Marble>)>,
+    marble: Query<&Transform, (With<Marble>, Without<Floor>)>,
+) {
+    let (Some(current_maze), Ok(floor), Ok(marble)) =
+        (current_maze, floor.get_single(), marble.get_single())
+    else {
+        return;
+    };
+    // Transforms rather than global ones, which aren't set yet for a new maze
+    let position = floor
+        .compute_affine()
+        .inverse()
+        .transform_point3(marble.translation);
+    let at_goal = current_maze.cell_at(position) == Some(current_maze.goal());
+    if attract.timer.tick(time.delta()).finished() || at_goal {
+        attract.renew = true;
+    }
+}
+
+pub(crate) fn needs_new_maze(attract: Res<AttractMaze>) -> bool {
+    attract.renew
+}
+
+pub(crate) fn steer_marble(
+    current_maze: Option<Res<CurrentMaze>>,
+    floor: Query<&Transform, (With<Floor>, Without<Marble>)>,
+    marble: Query<(&Transform, &Velocity), (With<Marble>, Without<Floor>)>,
+    mut tilt: ResMut<TiltInput>,
+) {
+    let (Some(current_maze), Ok(floor), Ok((marble, velocity))) =
+        (current_maze, floor.get_single(), marble.get_single())
+    else {
+        return;
+    };
+    if let Some(input) = autopilot::steer(&current_maze, floor, marble, velocity) {
+        *tilt = input;
+    }
+}
+
+pub(crate) fn orbit_camera(
+    time: Res<Time>,
+    mut camera: Query<&mut Transform, With<AttractCamera>>,
+) {
+    let angle = time.elapsed_seconds() / ORBIT_PERIOD * TAU;
+    for mut transform in &mut camera {
+        *transform = Transform::from_xyz(
+            angle.cos() * CAMERA_DISTANCE,
+            CAMERA_HEIGHT,
+            angle.sin() * CAMERA_DISTANCE,
+        )
+        .looking_at(Vec3::ZERO, Vec3::Y);
+    }
+}
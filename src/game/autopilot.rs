@@ -0,0 +1,222 @@
+//! Autopilot
+//!
+//! A bot bringing the marble to the goal by tilting the board, like a
+//! player would : it only sets the [TiltInput] of each physics step, so its
+//! runs can be recorded and replayed. Toggled with P, each game starts
+//! without it.
+//!
+//! It follows the way to the goal cell by cell, aiming at the center of the
+//! next cell with a PD controller : the wanted acceleration gives the tilt
+//! the floor should have, and the floor is tilted toward it no faster than
+//! with a gamepad. Keys and gems off the way aren't fetched.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::resources::CurrentMaze;
+
+use super::board::{Floor, Marble, Step, TiltInput, ANGLE_INCREMENT, GAMEPAD_SPEED_MULTIPLIER};
+
+/// Acceleration of a marble rolling on a slope, per radian of tilt.
+const ROLLING_ACCELERATION: f32 = 9.81 * 5. / 7.;
+/// Proportional gain, on the distance to the target.
+const KP: f32 = 10.;
+/// Derivative gain, on the velocity.
+const KD: f32 = 5.;
+const MAX_TILT: f32 = 0.12;
+/// How far from the axis of the corridor the marble can be, relative to the
+/// cell size, before it is brought back to the cell center.
+const AXIS_TOLERANCE: f32 = 0.15;
+/// Physics steps spent in the same cell before the autopilot is considered
+/// stuck.
+const STUCK_STEPS: u32 = 600;
+
+#[derive(Resource, Default)]
+pub(crate) struct Autopilot {
+    pub(crate) enabled: bool,
+    /// Cell the marble is in, with the step it entered it.
+    progress: Option<(u32, (i32, i32))>,
+}
+
+pub(crate) fn toggle_autopilot(keyboard: Res<Input<KeyCode>>, mut autopilot: ResMut<Autopilot>) {
+    if keyboard.just_pressed(KeyCode::P) {
+        autopilot.enabled = !autopilot.enabled;
+        autopilot.progress = None;
+        info!("Autopilot {}", if autopilot.enabled { "on" } else { "off" });
+    }
+}
+
+/// Give the board back to the player when the game is left.
+pub(crate) fn reset_autopilot(mut autopilot: ResMut<Autopilot>) {
+    *autopilot = Autopilot::default();
+}
+
+/// Tilt input bringing the marble toward the goal.
+pub(crate) fn steer(
+    current_maze: &CurrentMaze,
+    floor: &Transform,
+    marble: &Transform,
+    velocity: &Velocity,
+) -> Option<TiltInput> {
+    // Work in the floor space, where the maze is axis aligned
+    let position = floor
+        .compute_affine()
+        .inverse()
+        .transform_point3(marble.translation);
+    let velocity = floor.rotation.inverse() * velocity.linvel;
+    let cell = current_maze.cell_at(position)?;
+    let center = current_maze.cell_center(cell);
+    let path = current_maze.path(cell, current_maze.goal());
+
+    let target = match path.get(1) {
+        Some(next) => {
+            // Get back on the axis of the corridor first, not to hit its corner
+            let tolerance = current_maze.cell_size().min_element() * AXIS_TOLERANCE;
+            let off_axis = if next.x != cell.x {
+                (position.z - center.z).abs()
+            } else {
+                (position.x - center.x).abs()
+            };
+            if off_axis > tolerance {
+                center
+            } else {
+                current_maze.cell_center(*next)
+            }
+        }
+        None => center,
+    };
+
+    let acceleration = (target - position) * KP - velocity * KD;
+    // Going down along +z needs a positive rotation around x, along +x a
+    // negative one around z
+    let wanted_x = (acceleration.z / ROLLING_ACCELERATION).clamp(-MAX_TILT, MAX_TILT);
+    let wanted_z = (-acceleration.x / ROLLING_ACCELERATION).clamp(-MAX_TILT, MAX_TILT);
+    let (tilt_x, _, tilt_z) = floor.rotation.to_euler(EulerRot::XYZ);
+
+    Some(TiltInput {
+        x: ((wanted_x - tilt_x) / ANGLE_INCREMENT)
+            .clamp(-GAMEPAD_SPEED_MULTIPLIER, GAMEPAD_SPEED_MULTIPLIER),
+        z: ((wanted_z - tilt_z) / ANGLE_INCREMENT)
+            .clamp(-GAMEPAD_SPEED_MULTIPLIER, GAMEPAD_SPEED_MULTIPLIER),
+    })
+}
+
+pub(crate) fn drive(
+    step: Res<Step>,
+    current_maze: Res<CurrentMaze>,
+    mut autopilot: ResMut<Autopilot>,
+    floor: Query<&Transform, (With<Floor>, Without<Marble>)>,
+    marble: Query<(&Transform, &Velocity), (With<Marble>, Without<Floor>)>,
+    mut tilt: ResMut<TiltInput>,
+) {
+    if !autopilot.enabled {
+        return;
+    }
+    let (Ok(floor), Ok((marble, velocity))) = (floor.get_single(), marble.get_single()) else {
+        return;
+    };
+    let Some(input) = steer(&current_maze, floor, marble, velocity) else {
+        return;
+    };
+    *tilt = input;
+
+    // Warn when a board can't be solved, a wall being in the way for example
+    let position = floor
+        .compute_affine()
+        .inverse()
+        .transform_point3(marble.translation);
+    let Some(cell) = current_maze.cell_at(position) else {
+        return;
+    };
+    match autopilot.progress {
+        Some((since, last)) if last == (cell.x, cell.y) => {
+            if step.0.saturating_sub(since) == STUCK_STEPS {
+                warn!("Autopilot stuck in cell ({}, {})", cell.x, cell.y);
+            }
+        }
+        _ => autopilot.progress = Some((step.0, (cell.x, cell.y))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use crate::{
+        game::{
+            board::{self, GoalReached, TiltRate, STEP},
+            gems::Gems,
+            hud::{GameTimer, RunStats},
+            PhysicsPlugin,
+        },
+        resources::{GameMode, GemConfig, MazeConfig},
+        AppState,
+    };
+
+    use super::*;
+
+    /// Let the autopilot play a seeded maze without rendering, with the same
+    /// physics step as the game, and tell whether it reached the goal in time.
+    fn reaches_goal(seed: u64, seconds: f32) -> bool {
+        let mut config = MazeConfig::default();
+        config.set_width(6);
+        config.set_height(6);
+        config.set_seed(Some(seed));
+
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+            PhysicsPlugin,
+        ))
+        .add_asset::<Mesh>()
+        .add_asset::<StandardMaterial>()
+        .add_state::<AppState>()
+        .add_event::<GoalReached>()
+        .insert_resource(board::new_maze(&config))
+        .insert_resource(Autopilot {
+            enabled: true,
+            ..default()
+        })
+        .init_resource::<TiltRate>()
+        .init_resource::<Gems>()
+        .init_resource::<GemConfig>()
+        .init_resource::<GameTimer>()
+        .init_resource::<RunStats>()
+        .init_resource::<GameMode>()
+        // One physics step per update
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            STEP,
+        )))
+        .add_systems(Startup, board::setup_board)
+        .add_systems(
+            FixedUpdate,
+            (drive, board::apply_tilt)
+                .chain()
+                .before(PhysicsSet::SyncBackend),
+        )
+        .add_systems(
+            FixedUpdate,
+            board::detect_end_game.after(PhysicsSet::Writeback),
+        );
+
+        for _ in 0..(seconds / STEP) as usize {
+            app.update();
+            if !app.world.resource::<Events<GoalReached>>().is_empty() {
+                return true;
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn autopilot_reaches_the_goal() {
+        for seed in 0..3 {
+            assert!(reaches_goal(seed, 60.), "goal not reached with seed {seed}");
+        }
+    }
+}
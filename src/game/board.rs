@@ -1,17 +1,35 @@
-use std::f32::consts::PI;
+use std::{collections::HashSet, f32::consts::PI};
 
-use crate::{resources::MazeConfig, AppState};
+use crate::{
+    resources::{Controls, CurrentMaze, GameMode, GemConfig, MazeConfig},
+    results::Results,
+    AppState,
+};
 
-use super::GameComponent;
+use super::{
+    gems::Gems,
+    hud::{GameTimer, RunStats},
+    GameComponent,
+};
 use bevy::prelude::*;
 use bevy_rapier3d::{prelude::*, rapier::prelude::CollisionEventFlags};
-use maze_generator::prelude::{Coordinates, Direction as MazeDirection, Maze};
+use maze_generator::prelude::{Coordinates, Direction as MazeDirection, FieldType, Maze};
+use serde::{Deserialize, Serialize};
 
 const PLAN_SIZE: f32 = 5.;
-const BORDER_HEIGHT: f32 = 0.3;
-const ANGLE_INCREMENT: f32 = PI / 720.;
+pub(crate) const BORDER_HEIGHT: f32 = 0.3;
+pub(crate) const ANGLE_INCREMENT: f32 = PI / 720.;
 const GAMEPAD_DEAD_ZONE: f32 = 0.2;
-const GAMEPAD_SPEED_MULTIPLIER: f32 = 1.8;
+pub(crate) const GAMEPAD_SPEED_MULTIPLIER: f32 = 1.8;
+pub(crate) const MARBLE_RADIUS: f32 = 0.1;
+/// Duration of a physics step, in seconds.
+pub(crate) const STEP: f32 = 1. / 60.;
+/// Space between the boards of a versus race.
+const BOARD_GAP: f32 = 2.;
+/// Keys tilting the floor up, down, left and right, on each half of the
+/// keyboard.
+const LEFT_KEYS: [KeyCode; 4] = [KeyCode::Z, KeyCode::S, KeyCode::Q, KeyCode::D];
+const RIGHT_KEYS: [KeyCode; 4] = [KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right];
 
 #[derive(Component)]
 pub(crate) struct Floor;
@@ -19,6 +37,137 @@ pub(crate) struct Floor;
 #[derive(Component)]
 pub(crate) struct Wall;
 
+/// Outer walls of the board, on top of [Wall].
+#[derive(Component)]
+pub(crate) struct Border;
+
+/// The marble the player has to bring to the goal.
+#[derive(Component)]
+pub(crate) struct Marble {
+    /// Position of the start cell, used when restarting.
+    pub(crate) start: Vec3,
+    /// Position of the last checkpoint reached, if any.
+    pub(crate) checkpoint: Option<Vec3>,
+}
+
+impl Marble {
+    /// Where to put the marble back when it is lost.
+    pub(crate) fn respawn_position(&self) -> Vec3 {
+        self.checkpoint.unwrap_or(self.start)
+    }
+}
+
+/// Owner of a board when several are played, on its floor, marble and camera.
+#[derive(Component, Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Player {
+    /// Left half of the keyboard and first gamepad.
+    One,
+    /// Arrows and second gamepad.
+    Two,
+}
+
+impl Player {
+    pub(crate) const ALL: [Player; 2] = [Player::One, Player::Two];
+
+    pub(crate) fn index(self) -> usize {
+        match self {
+            Player::One => 0,
+            Player::Two => 1,
+        }
+    }
+
+    /// Center of the board of the player, the boards being side by side.
+    pub(crate) fn board_center(self) -> Vec3 {
+        let x = (PLAN_SIZE + BOARD_GAP) / 2.;
+        match self {
+            Player::One => Vec3::new(-x, 0., 0.),
+            Player::Two => Vec3::new(x, 0., 0.),
+        }
+    }
+
+    /// Tilt asked by the player with their keys and gamepad.
+    pub(crate) fn input(
+        self,
+        keyboard: &Input<KeyCode>,
+        gamepads: &Gamepads,
+        axes: &Axis<GamepadAxis>,
+    ) -> TiltInput {
+        let controls = match self {
+            Player::One => [Controls::LeftKeys, Controls::Gamepad(0)],
+            Player::Two => [Controls::RightKeys, Controls::Gamepad(1)],
+        };
+        controls_input(&controls, keyboard, gamepads, axes)
+    }
+}
+
+/// Sensor of the goal cell.
+#[derive(Component)]
+pub(crate) struct Goal;
+
+/// Cells where an element has already been placed, so the other ones are
+/// put elsewhere.
+#[derive(Resource, Default)]
+pub(crate) struct OccupiedCells {
+    /// Cells with a hole, they block the way.
+    pub(crate) holes: HashSet<Coordinates>,
+    /// Cells with an item (gem, key...).
+    pub(crate) items: HashSet<Coordinates>,
+}
+
+/// Multiplier of the speed at which the floor is tilted.
+#[derive(Resource)]
+pub(crate) struct TiltRate(pub(crate) f32);
+
+impl Default for TiltRate {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// Rotation of the floor asked for the next physics step, around its `x` and
+/// `z` axis, in [ANGLE_INCREMENT] units.
+///
+/// When several boards are played, each floor has its own one.
+#[derive(Resource, Component, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct TiltInput {
+    pub(crate) x: f32,
+    pub(crate) z: f32,
+}
+
+/// Physics steps since the beginning of the run, the clock of everything the
+/// physics depends on.
+#[derive(Resource, Default)]
+pub(crate) struct Step(pub(crate) u32);
+
+/// An element rotating around the `y` axis, in radians per second.
+#[derive(Component)]
+pub(crate) struct Spinning(pub(crate) f32);
+
+/// Sent when the marble has been lost (fell in a hole...) and must be
+/// put back on the board.
+#[derive(Event)]
+pub(crate) struct MarbleLost;
+
+/// Sent when the marble reached the goal.
+#[derive(Event)]
+pub(crate) struct GoalReached {
+    /// Seconds elapsed, penalties included.
+    pub(crate) time: f32,
+    pub(crate) score: u32,
+}
+
+/// Level the floor and put the marble at `position`, without any velocity.
+pub(crate) fn place_marble(
+    floor: &mut Transform,
+    marble: &mut Transform,
+    velocity: &mut Velocity,
+    position: Vec3,
+) {
+    floor.rotation = Quat::IDENTITY;
+    *marble = Transform::from_translation(position);
+    *velocity = Velocity::zero();
+}
+
 fn spwan_wall(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
@@ -27,7 +176,7 @@ fn spwan_wall(
     length: f32,
     position: Vec3,
     rotation: Quat,
-) {
+) -> Entity {
     let mesh = Mesh::from(shape::Box::new(length, BORDER_HEIGHT, 0.001));
     let collider = Collider::from_bevy_mesh(&mesh, &ComputedColliderShape::TriMesh).unwrap();
     let wall = commands
@@ -43,23 +192,34 @@ fn spwan_wall(
         .insert(collider)
         .id();
     commands.entity(floor).add_child(wall);
+    wall
+}
+
+/// Generate the maze of `config`, with a random seed if it has none.
+pub(crate) fn new_maze(config: &MazeConfig) -> CurrentMaze {
+    let config = config.with_random_seed().unwrap();
+    let maze: Maze = (&config).try_into().unwrap();
+    CurrentMaze::new(maze, config, PLAN_SIZE)
+}
+
+/// Generate the maze that will be played and make it available as [CurrentMaze].
+pub(crate) fn generate_maze(mut commands: Commands, config: Res<MazeConfig>) {
+    commands.insert_resource(new_maze(&config));
+    commands.insert_resource(OccupiedCells::default());
+}
+
+pub(crate) fn remove_maze(mut commands: Commands) {
+    commands.remove_resource::<CurrentMaze>();
 }
 
 pub(crate) fn setup_board(
     mut commands: Commands,
-    maze: Res<MazeConfig>,
+    current_maze: Res<CurrentMaze>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    // Perhaps, instead of handling walls by myself, use rapier joints (FixedJoint),
-    // puting an anchor of both lower corner of the wall.
-
-    commands
-        .spawn(Camera3dBundle {
-            transform: Transform::from_xyz(0., 7., 4.).looking_at(Vec3::ZERO, Vec3::Y),
-            ..default()
-        })
-        .insert(GameComponent);
+    commands.insert_resource(Step::default());
+    commands.insert_resource(TiltInput::default());
 
     // light
     commands
@@ -74,6 +234,27 @@ pub(crate) fn setup_board(
         })
         .insert(GameComponent);
 
+    spawn_board(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &current_maze,
+        Vec3::ZERO,
+    );
+}
+
+/// Spawn the floor of the maze centered on `center`, with its walls and
+/// marble, and return the floor and the marble.
+pub(crate) fn spawn_board(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    current_maze: &CurrentMaze,
+    center: Vec3,
+) -> (Entity, Entity) {
+    // Perhaps, instead of handling walls by myself, use rapier joints (FixedJoint),
+    // puting an anchor of both lower corner of the wall.
+
     // Floor
     let mesh = Mesh::from(shape::Plane::from_size(PLAN_SIZE));
     let collider = Collider::from_bevy_mesh(&mesh, &ComputedColliderShape::TriMesh).unwrap();
@@ -81,6 +262,7 @@ pub(crate) fn setup_board(
         .spawn(PbrBundle {
             mesh: meshes.add(mesh),
             material: materials.add(Color::rgb(0.7, 0.7, 0.7).into()),
+            transform: Transform::from_translation(center),
             ..default()
         })
         .insert(GameComponent)
@@ -90,55 +272,61 @@ pub(crate) fn setup_board(
         .id();
 
     // Front wall
-    spwan_wall(
-        &mut commands,
-        &mut meshes,
-        &mut materials,
+    let wall = spwan_wall(
+        commands,
+        meshes,
+        materials,
         floor,
         PLAN_SIZE,
         Vec3::new(0., BORDER_HEIGHT / 2., PLAN_SIZE / 2.),
         Quat::IDENTITY,
     );
+    commands.entity(wall).insert(Border);
 
     // Back wall
-    spwan_wall(
-        &mut commands,
-        &mut meshes,
-        &mut materials,
+    let wall = spwan_wall(
+        commands,
+        meshes,
+        materials,
         floor,
         PLAN_SIZE,
         Vec3::new(0., BORDER_HEIGHT / 2., -PLAN_SIZE / 2.),
         Quat::IDENTITY,
     );
+    commands.entity(wall).insert(Border);
 
     let rotation = Quat::from_rotation_y(PI / 2.);
     // Left wall
-    spwan_wall(
-        &mut commands,
-        &mut meshes,
-        &mut materials,
+    let wall = spwan_wall(
+        commands,
+        meshes,
+        materials,
         floor,
         PLAN_SIZE,
         Vec3::new(-PLAN_SIZE / 2., BORDER_HEIGHT / 2., 0.),
         rotation,
     );
+    commands.entity(wall).insert(Border);
 
     // Right wall
-    spwan_wall(
-        &mut commands,
-        &mut meshes,
-        &mut materials,
+    let wall = spwan_wall(
+        commands,
+        meshes,
+        materials,
         floor,
         PLAN_SIZE,
         Vec3::new(PLAN_SIZE / 2., BORDER_HEIGHT / 2., 0.),
         rotation,
     );
+    commands.entity(wall).insert(Border);
 
     // Spawn maze walls
-    let maze: Maze = maze.as_ref().try_into().unwrap();
-    let (width, height) = maze.size;
-    let z_length = PLAN_SIZE / height as f32;
-    let x_length = PLAN_SIZE / width as f32;
+    let mut marble = None;
+    let maze = current_maze.maze();
+    let (width, height) = current_maze.size();
+    let cell_size = current_maze.cell_size();
+    let z_length = cell_size.y;
+    let x_length = cell_size.x;
     for line in 0..height {
         for column in 0..width {
             // Optimization : we can spawn only on corner for room, here only north and west walls (missing walls
@@ -150,32 +338,47 @@ pub(crate) fn setup_board(
                 let z_position = (line as f32 * z_length) - (PLAN_SIZE / 2.);
 
                 match field.field_type {
-                    maze_generator::prelude::FieldType::Start => {
+                    FieldType::Start => {
                         // Spawn marble
                         let mesh = Mesh::try_from(shape::Icosphere {
-                            radius: 0.1,
+                            radius: MARBLE_RADIUS,
                             subdivisions: 5,
                         })
                         .unwrap();
-                        commands
+                        // The marble isn't a child of the floor
+                        let start = center
+                            + Vec3::new(
+                                x_position + x_length / 2.,
+                                0.11,
+                                z_position + z_length / 2.,
+                            );
+                        let entity = commands
                             .spawn(PbrBundle {
                                 mesh: meshes.add(mesh),
                                 material: materials.add(Color::rgb(0., 0., 1.).into()),
                                 ..default()
                             })
                             .insert(GameComponent)
+                            .insert(Marble {
+                                start,
+                                checkpoint: None,
+                            })
                             .insert(RigidBody::Dynamic)
-                            .insert(Collider::ball(0.1))
+                            .insert(Collider::ball(MARBLE_RADIUS))
+                            .insert(Velocity::zero())
+                            .insert(Friction::default())
+                            .insert(Damping::default())
+                            .insert(ExternalForce::default())
+                            .insert(ExternalImpulse::default())
+                            .insert(ReadMassProperties::default())
                             .insert(ActiveEvents::COLLISION_EVENTS)
                             .insert(Restitution::coefficient(0.7))
-                            .insert(TransformBundle::from(Transform::from_xyz(
-                                x_position + x_length / 2.,
-                                0.11,
-                                z_position + z_length / 2.,
-                            )))
-                            .insert(GameComponent);
+                            .insert(TransformBundle::from(Transform::from_translation(start)))
+                            .insert(GameComponent)
+                            .id();
+                        marble = Some(entity);
                     }
-                    maze_generator::prelude::FieldType::Goal => {
+                    FieldType::Goal => {
                         // Spaw box with transparency and no collider to show the goal.
                         let mesh = Mesh::from(shape::Box::new(
                             x_length - 0.01,
@@ -204,6 +407,7 @@ pub(crate) fn setup_board(
                                 Vec3::new(0., BORDER_HEIGHT, 0.),
                             ))
                             .insert(Sensor)
+                            .insert(Goal)
                             .insert(TransformBundle::from_transform(
                                 Transform::from_translation(Vec3::new(
                                     x_position + x_length / 2.,
@@ -216,14 +420,14 @@ pub(crate) fn setup_board(
                             .id();
                         commands.entity(floor).add_child(goal_detection);
                     }
-                    maze_generator::prelude::FieldType::Normal => (),
+                    FieldType::Normal => (),
                 }
 
                 if line != 0 && !field.has_passage(&MazeDirection::North) {
                     spwan_wall(
-                        &mut commands,
-                        &mut meshes,
-                        &mut materials,
+                        commands,
+                        meshes,
+                        materials,
                         floor,
                         x_length,
                         Vec3::new(x_position + x_length / 2., BORDER_HEIGHT / 2., z_position),
@@ -232,9 +436,9 @@ pub(crate) fn setup_board(
                 }
                 if column != 0 && !field.has_passage(&MazeDirection::West) {
                     spwan_wall(
-                        &mut commands,
-                        &mut meshes,
-                        &mut materials,
+                        commands,
+                        meshes,
+                        materials,
                         floor,
                         x_length,
                         Vec3::new(x_position, BORDER_HEIGHT / 2., z_position + z_length / 2.),
@@ -255,80 +459,195 @@ pub(crate) fn setup_board(
         .insert(GameComponent)
         .id();
     commands.entity(floor).add_child(top);
+
+    (floor, marble.expect("the maze has a start cell"))
+}
+
+/// Tilt asked with any of the `keys` sets, see [LEFT_KEYS].
+fn keyboard_tilt(keyboard: &Input<KeyCode>, keys: &[[KeyCode; 4]]) -> TiltInput {
+    let pressed = |index: usize| keys.iter().any(|keys| keyboard.pressed(keys[index]));
+    TiltInput {
+        x: if pressed(0) {
+            -1.
+        } else if pressed(1) {
+            1.
+        } else {
+            0.
+        },
+        z: if pressed(3) {
+            -1.
+        } else if pressed(2) {
+            1.
+        } else {
+            0.
+        },
+    }
+}
+
+/// Tilt asked with the left stick of `gamepad`.
+fn gamepad_tilt(axes: &Axis<GamepadAxis>, gamepad: Gamepad) -> TiltInput {
+    let mut tilt = TiltInput::default();
+
+    // Rotation around X axis (when we move up and down => gamepad Y axis)
+    let left_stick_y = axes
+        .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+        .unwrap_or_default();
+    if !(-GAMEPAD_DEAD_ZONE..=GAMEPAD_DEAD_ZONE).contains(&left_stick_y) {
+        tilt.x -= left_stick_y * GAMEPAD_SPEED_MULTIPLIER;
+    }
+
+    // Rotation around Z axis (when we move left and right => gamepad X axis)
+    let left_stick_x = axes
+        .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+        .unwrap_or_default();
+    if !(-GAMEPAD_DEAD_ZONE..=GAMEPAD_DEAD_ZONE).contains(&left_stick_x) {
+        tilt.z -= left_stick_x * GAMEPAD_SPEED_MULTIPLIER;
+    }
+    tilt
 }
 
+/// Tilt asked with any of the `controls`.
+pub(crate) fn controls_input(
+    controls: &[Controls],
+    keyboard: &Input<KeyCode>,
+    gamepads: &Gamepads,
+    axes: &Axis<GamepadAxis>,
+) -> TiltInput {
+    // Gamepads are numbered in the order they were connected
+    let mut connected: Vec<Gamepad> = gamepads.iter().collect();
+    connected.sort_by_key(|gamepad| gamepad.id);
+
+    let mut tilt = TiltInput::default();
+    for controls in controls {
+        let input = match controls {
+            Controls::LeftKeys => keyboard_tilt(keyboard, &[LEFT_KEYS]),
+            Controls::RightKeys => keyboard_tilt(keyboard, &[RIGHT_KEYS]),
+            Controls::Gamepad(index) => connected
+                .get(*index)
+                .map(|gamepad| gamepad_tilt(axes, *gamepad))
+                .unwrap_or_default(),
+        };
+        tilt.x += input.x;
+        tilt.z += input.z;
+    }
+    tilt
+}
+
+pub(crate) fn handle_keyboard_input(keyboard: Res<Input<KeyCode>>, mut tilt: ResMut<TiltInput>) {
+    *tilt = keyboard_tilt(&keyboard, &[LEFT_KEYS, RIGHT_KEYS]);
+}
+
+/// Add the gamepads input to the keyboard one.
 pub(crate) fn handle_gamepad_input(
     gamepads: Res<Gamepads>,
     axes: Res<Axis<GamepadAxis>>,
-    mut floor: Query<&mut Transform, (With<Floor>, Without<Wall>)>,
+    mut tilt: ResMut<TiltInput>,
 ) {
-    if let Ok(mut floor) = floor.get_single_mut() {
-        for gamepad in gamepads.iter() {
-            // Rotation around X axis (when we move up and down => gamepad Y axis)
-            let left_stick_y = axes
-                .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
-                .unwrap_or_default();
-            if !(-GAMEPAD_DEAD_ZONE..=GAMEPAD_DEAD_ZONE).contains(&left_stick_y) {
-                let angle_x = left_stick_y * ANGLE_INCREMENT * GAMEPAD_SPEED_MULTIPLIER;
-                floor.rotate_local_x(-angle_x);
-            }
+    for gamepad in gamepads.iter() {
+        let gamepad_tilt = gamepad_tilt(&axes, gamepad);
+        tilt.x += gamepad_tilt.x;
+        tilt.z += gamepad_tilt.z;
+    }
+}
 
-            // Rotation around Z axis (when we move left and right => gamepad X axis)
-            let left_stick_x = axes
-                .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
-                .unwrap_or_default();
-            if !(-GAMEPAD_DEAD_ZONE..=GAMEPAD_DEAD_ZONE).contains(&left_stick_x) {
-                let angle_z = left_stick_x * ANGLE_INCREMENT * GAMEPAD_SPEED_MULTIPLIER;
-                floor.rotate_local_z(-angle_z);
-            }
-        }
+/// Tilt the floors, once per physics step so a run only depends on the
+/// [TiltInput] of each step.
+pub(crate) fn apply_tilt(
+    tilt: Res<TiltInput>,
+    tilt_rate: Res<TiltRate>,
+    mut step: ResMut<Step>,
+    mut floors: Query<(&mut Transform, Option<&TiltInput>), (With<Floor>, Without<Wall>)>,
+) {
+    step.0 += 1;
+    for (mut floor, own_tilt) in &mut floors {
+        let tilt = own_tilt.copied().unwrap_or(*tilt);
+        floor.rotate_local_x(tilt.x * ANGLE_INCREMENT * tilt_rate.0);
+        floor.rotate_local_z(tilt.z * ANGLE_INCREMENT * tilt_rate.0);
     }
 }
 
-pub(crate) fn handle_keyboard_input(
-    keyboard: Res<Input<KeyCode>>,
-    mut floor: Query<&mut Transform, (With<Floor>, Without<Wall>)>,
+pub(crate) fn spin(mut spinning: Query<(&mut Transform, &Spinning)>) {
+    for (mut transform, Spinning(speed)) in &mut spinning {
+        transform.rotate_y(speed * STEP);
+    }
+}
+
+pub(crate) fn respawn_marble(
+    mut lost: EventReader<MarbleLost>,
+    mut floors: Query<(&mut Transform, Option<&Player>), (With<Floor>, Without<Marble>)>,
+    mut marbles: Query<(&Marble, &mut Transform, &mut Velocity, Option<&Player>), Without<Floor>>,
 ) {
-    if !keyboard.pressed(KeyCode::S)
-        && !keyboard.pressed(KeyCode::Down)
-        && !keyboard.pressed(KeyCode::Q)
-        && !keyboard.pressed(KeyCode::Left)
-        && !keyboard.pressed(KeyCode::Z)
-        && !keyboard.pressed(KeyCode::Up)
-        && !keyboard.pressed(KeyCode::D)
-        && !keyboard.pressed(KeyCode::Right)
-    {
+    if lost.is_empty() {
         return;
     }
-    if let Ok(mut floor) = floor.get_single_mut() {
-        let angle_x = if keyboard.pressed(KeyCode::Z) || keyboard.pressed(KeyCode::Up) {
-            -ANGLE_INCREMENT
-        } else if keyboard.pressed(KeyCode::S) || keyboard.pressed(KeyCode::Down) {
-            ANGLE_INCREMENT
-        } else {
-            0.
-        };
-        let angle_z = if keyboard.pressed(KeyCode::D) || keyboard.pressed(KeyCode::Right) {
-            -ANGLE_INCREMENT
-        } else if keyboard.pressed(KeyCode::Q) || keyboard.pressed(KeyCode::Left) {
-            ANGLE_INCREMENT
-        } else {
-            0.
-        };
-        floor.rotate_local_x(angle_x);
-        floor.rotate_local_z(angle_z);
+    lost.clear();
+
+    for (marble, mut transform, mut velocity, player) in &mut marbles {
+        if let Some((mut floor, _)) = floors.iter_mut().find(|(_, owner)| *owner == player) {
+            place_marble(
+                &mut floor,
+                &mut transform,
+                &mut velocity,
+                marble.respawn_position(),
+            );
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn detect_end_game(
+    mut commands: Commands,
     mut collision_events: EventReader<CollisionEvent>,
+    goals: Query<(), With<Goal>>,
+    gems: Res<Gems>,
+    gem_config: Res<GemConfig>,
+    timer: Res<GameTimer>,
+    mut stats: ResMut<RunStats>,
+    mode: Res<GameMode>,
+    mut reached: EventWriter<GoalReached>,
     mut state: ResMut<NextState<AppState>>,
 ) {
+    // The winner of a race is decided in super::versus
+    if *mode == GameMode::Versus {
+        collision_events.clear();
+        return;
+    }
     for collision_event in collision_events.iter() {
-        if let CollisionEvent::Started(_, _, flags) = collision_event {
-            if flags.intersects(CollisionEventFlags::SENSOR) {
-                info!("Reach goal");
-                state.set(AppState::Menu);
+        if let CollisionEvent::Started(first, second, flags) = collision_event {
+            if flags.intersects(CollisionEventFlags::SENSOR)
+                && (goals.contains(*first) || goals.contains(*second))
+            {
+                if gems.collected < gems.required {
+                    info!("{} more gems needed", gems.required - gems.collected);
+                    continue;
+                }
+                if gems.total > 0 && gems.collected == gems.total {
+                    stats.bonus += gem_config.bonus;
+                }
+                info!(
+                    "Reach goal in {:.2}s, score {} ({} faults, {} falls, {} hints)",
+                    timer.elapsed_secs(),
+                    stats.score(timer.elapsed_secs()),
+                    stats.faults,
+                    stats.falls,
+                    stats.hints_used
+                );
+                reached.send(GoalReached {
+                    time: timer.elapsed_secs(),
+                    score: stats.score(timer.elapsed_secs()),
+                });
+                // Other modes decide what comes next
+                if *mode == GameMode::Free {
+                    let time = timer.elapsed_secs();
+                    commands.insert_resource(Results::new(
+                        "Goal reached",
+                        vec![
+                            format!("Time : {time:.2}s"),
+                            format!("Score : {}", stats.score(time)),
+                        ],
+                    ));
+                    state.set(AppState::Results);
+                }
             }
         }
     }
@@ -0,0 +1,231 @@
+//! Bumpers and gravity wells
+//!
+//! Bumpers stand at the end of dead ends and kick the marble back when it
+//! hits them. Wells attract or repel the marble around them with a force
+//! fading with the distance (see [BumperConfig]).
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use maze_generator::prelude::Coordinates;
+use rand::seq::SliceRandom;
+
+use crate::resources::{BumperConfig, CurrentMaze};
+
+use super::{
+    board::{Floor, Marble, OccupiedCells, Spinning, BORDER_HEIGHT},
+    GameComponent,
+};
+
+const BUMPER_SALT: u64 = 0x62756d70;
+const BUMPER_COLOR: Color = Color::rgb(1., 0.8, 0.1);
+/// Radius of wells, in cells.
+const WELL_RADIUS: f32 = 1.5;
+const ATTRACTOR_COLOR: Color = Color::rgba(0.2, 0.4, 1., 0.4);
+const REPULSOR_COLOR: Color = Color::rgba(1., 0.2, 0.2, 0.4);
+const WELL_SPINNING_SPEED: f32 = 1.;
+
+#[derive(Component)]
+pub(crate) struct Bumper;
+
+/// Field attracting (positive strength) or repelling (negative strength) the marble.
+#[derive(Component)]
+pub(crate) struct Well {
+    /// Center, in the floor local space.
+    position: Vec3,
+    radius: f32,
+    strength: f32,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn setup_bumpers(
+    mut commands: Commands,
+    config: Res<BumperConfig>,
+    current_maze: Res<CurrentMaze>,
+    mut occupied: ResMut<OccupiedCells>,
+    floor: Query<Entity, With<Floor>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !config.enabled {
+        return;
+    }
+    let Ok(floor) = floor.get_single() else {
+        return;
+    };
+
+    let mut rng = current_maze.rng(BUMPER_SALT);
+    let cell_size = current_maze.cell_size();
+
+    // Bumpers, at the end of dead ends so they never block the way
+    let mut dead_ends: Vec<Coordinates> = current_maze
+        .dead_ends()
+        .into_iter()
+        .filter(|cell| !occupied.holes.contains(cell) && !occupied.items.contains(cell))
+        .collect();
+    dead_ends.shuffle(&mut rng);
+    dead_ends.truncate(config.bumpers);
+
+    let radius = cell_size.min_element() * 0.18;
+    let height = BORDER_HEIGHT * 0.6;
+    let mesh = meshes.add(Mesh::from(shape::Cylinder {
+        radius,
+        height,
+        ..default()
+    }));
+    let material = materials.add(StandardMaterial {
+        base_color: BUMPER_COLOR,
+        emissive: BUMPER_COLOR * 0.2,
+        ..default()
+    });
+    for cell in dead_ends {
+        occupied.items.insert(cell);
+        let center = current_maze.cell_center(cell);
+        let away = current_maze
+            .neighbours(cell)
+            .first()
+            .map(|neighbour| (center - current_maze.cell_center(*neighbour)).normalize())
+            .unwrap_or_default();
+        let position = center + away * cell_size.min_element() * 0.2 + Vec3::Y * height / 2.;
+        let bumper = commands
+            .spawn(PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_translation(position),
+                ..default()
+            })
+            .insert(RigidBody::Fixed)
+            .insert(Collider::cylinder(height / 2., radius))
+            .insert(Restitution {
+                coefficient: 1.2,
+                combine_rule: CoefficientCombineRule::Max,
+            })
+            .insert(Bumper)
+            .insert(GameComponent)
+            .id();
+        commands.entity(floor).add_child(bumper);
+    }
+
+    // Wells, anywhere there is room
+    let mut cells: Vec<Coordinates> = current_maze
+        .cells()
+        .filter(|cell| *cell != current_maze.start() && *cell != current_maze.goal())
+        .filter(|cell| !occupied.holes.contains(cell) && !occupied.items.contains(cell))
+        .collect();
+    cells.shuffle(&mut rng);
+    cells.truncate(config.wells);
+
+    let radius = cell_size.min_element() * WELL_RADIUS;
+    let mesh = meshes.add(Mesh::from(shape::Torus {
+        radius: radius * 0.3,
+        ring_radius: radius * 0.03,
+        ..default()
+    }));
+    let attractor = materials.add(StandardMaterial {
+        base_color: ATTRACTOR_COLOR,
+        emissive: ATTRACTOR_COLOR * 0.5,
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    });
+    let repulsor = materials.add(StandardMaterial {
+        base_color: REPULSOR_COLOR,
+        emissive: REPULSOR_COLOR * 0.5,
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    });
+    for (index, cell) in cells.into_iter().enumerate() {
+        occupied.items.insert(cell);
+        let attracting = index % 2 == 0;
+        let position = current_maze.cell_center(cell);
+        let well = commands
+            .spawn(PbrBundle {
+                mesh: mesh.clone(),
+                material: if attracting {
+                    attractor.clone()
+                } else {
+                    repulsor.clone()
+                },
+                transform: Transform::from_translation(position + Vec3::Y * 0.01),
+                ..default()
+            })
+            .insert(Well {
+                position,
+                radius,
+                strength: if attracting {
+                    config.well_strength
+                } else {
+                    -config.well_strength
+                },
+            })
+            .insert(Spinning(WELL_SPINNING_SPEED))
+            .insert(GameComponent)
+            .id();
+        commands.entity(floor).add_child(well);
+    }
+}
+
+/// Direction from the bumper to the marble, in the plane of the floor.
+fn kick_direction(floor: &GlobalTransform, bumper: Vec3, marble: Vec3) -> Vec3 {
+    let normal = floor.up();
+    let direction = marble - bumper;
+    (direction - normal * direction.dot(normal)).normalize_or_zero()
+}
+
+pub(crate) fn kick_marble(
+    mut collision_events: EventReader<CollisionEvent>,
+    config: Res<BumperConfig>,
+    bumpers: Query<&GlobalTransform, With<Bumper>>,
+    floor: Query<&GlobalTransform, With<Floor>>,
+    mut marble: Query<(&GlobalTransform, &ReadMassProperties, &mut ExternalImpulse), With<Marble>>,
+) {
+    let (Ok(floor), Ok((transform, mass, mut impulse))) =
+        (floor.get_single(), marble.get_single_mut())
+    else {
+        return;
+    };
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(first, second, _) = collision_event {
+            for entity in [first, second] {
+                if let Ok(bumper) = bumpers.get(*entity) {
+                    impulse.impulse +=
+                        kick_direction(floor, bumper.translation(), transform.translation())
+                            * config.bumper_kick
+                            * mass.0.mass;
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn apply_wells(
+    wells: Query<&Well>,
+    floor: Query<&GlobalTransform, With<Floor>>,
+    mut marble: Query<(&GlobalTransform, &ReadMassProperties, &mut ExternalForce), With<Marble>>,
+) {
+    let (Ok(floor), Ok((transform, mass, mut force))) =
+        (floor.get_single(), marble.get_single_mut())
+    else {
+        return;
+    };
+
+    // Compute in the floor local space, so the force stays in the floor plane
+    let position = floor
+        .affine()
+        .inverse()
+        .transform_point3(transform.translation());
+    let mut local_force = Vec3::ZERO;
+    for well in &wells {
+        let offset = Vec3::new(
+            well.position.x - position.x,
+            0.,
+            well.position.z - position.z,
+        );
+        let distance = offset.length();
+        if distance < well.radius && distance > f32::EPSILON {
+            local_force += offset / distance * well.strength * (1. - distance / well.radius);
+        }
+    }
+    let world_force = floor.affine().transform_vector3(local_force) * mass.0.mass;
+    if force.force != world_force {
+        force.force = world_force;
+    }
+}
@@ -0,0 +1,197 @@
+//! Game camera
+//!
+//! The camera can be fixed above the board, follow the marble, look at the
+//! board from the top with an orthographic projection, or orbit around the
+//! board using the mouse (while the right button is held) or the right stick.
+//!
+//! The mode is switched with `C` or the right stick button, and is kept in
+//! [CameraConfig] for the next games and sessions.
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseWheel},
+    prelude::*,
+    render::camera::ScalingMode,
+};
+
+use crate::resources::{CameraConfig, CameraMode, CurrentMaze};
+
+use super::{board::Marble, GameComponent};
+
+const FIXED_POSITION: Vec3 = Vec3::new(0., 7., 4.);
+const FOLLOW_OFFSET: Vec3 = Vec3::new(0., 3., 2.);
+const FOLLOW_SPEED: f32 = 4.;
+const TOP_DOWN_HEIGHT: f32 = 10.;
+const ORBIT_MOUSE_SPEED: f32 = 0.005;
+const ORBIT_GAMEPAD_SPEED: f32 = 2.;
+const ORBIT_ZOOM_SPEED: f32 = 0.5;
+const ORBIT_MIN_RADIUS: f32 = 3.;
+const ORBIT_MAX_RADIUS: f32 = 15.;
+const GAMEPAD_DEAD_ZONE: f32 = 0.2;
+
+#[derive(Component)]
+pub(crate) struct GameCamera;
+
+/// Position of the orbit camera around the center of the board.
+#[derive(Component)]
+pub(crate) struct Orbit {
+    yaw: f32,
+    pitch: f32,
+    radius: f32,
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        // Start from the fixed point of view
+        Self {
+            yaw: FIXED_POSITION.x.atan2(FIXED_POSITION.z),
+            pitch: FIXED_POSITION
+                .y
+                .atan2(Vec2::new(FIXED_POSITION.x, FIXED_POSITION.z).length()),
+            radius: FIXED_POSITION.length(),
+        }
+    }
+}
+
+impl Orbit {
+    fn position(&self) -> Vec3 {
+        Vec3::new(
+            self.radius * self.pitch.cos() * self.yaw.sin(),
+            self.radius * self.pitch.sin(),
+            self.radius * self.pitch.cos() * self.yaw.cos(),
+        )
+    }
+}
+
+fn projection(mode: CameraMode, board_size: f32) -> Projection {
+    match mode {
+        CameraMode::TopDown => OrthographicProjection {
+            scaling_mode: ScalingMode::FixedVertical(board_size * 1.1),
+            ..default()
+        }
+        .into(),
+        _ => PerspectiveProjection::default().into(),
+    }
+}
+
+pub(crate) fn setup_camera(
+    mut commands: Commands,
+    config: Res<CameraConfig>,
+    current_maze: Res<CurrentMaze>,
+) {
+    commands
+        .spawn(Camera3dBundle {
+            transform: Transform::from_translation(FIXED_POSITION).looking_at(Vec3::ZERO, Vec3::Y),
+            projection: projection(config.mode, current_maze.board_size()),
+            ..default()
+        })
+        .insert(GameCamera)
+        .insert(Orbit::default())
+        .insert(GameComponent);
+}
+
+pub(crate) fn switch_camera_mode(
+    keyboard: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    mut config: ResMut<CameraConfig>,
+    current_maze: Res<CurrentMaze>,
+    mut camera: Query<&mut Projection, With<GameCamera>>,
+) {
+    let switch = keyboard.just_pressed(KeyCode::C)
+        || gamepads.iter().any(|gamepad| {
+            buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::RightThumb))
+        });
+    if !switch {
+        return;
+    }
+
+    config.mode = config.mode.next();
+    config.save();
+    info!("Camera mode : {:?}", config.mode);
+    for mut projection_component in &mut camera {
+        *projection_component = projection(config.mode, current_maze.board_size());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handle_orbit_input(
+    config: Res<CameraConfig>,
+    time: Res<Time>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut orbits: Query<&mut Orbit, With<GameCamera>>,
+) {
+    if config.mode != CameraMode::Orbit {
+        // Don't keep old events for when we switch to orbit
+        mouse_motion.clear();
+        mouse_wheel.clear();
+        return;
+    }
+
+    let mut rotation = Vec2::ZERO;
+    if mouse_buttons.pressed(MouseButton::Right) {
+        rotation +=
+            mouse_motion.iter().map(|motion| motion.delta).sum::<Vec2>() * ORBIT_MOUSE_SPEED;
+    } else {
+        mouse_motion.clear();
+    }
+    for gamepad in gamepads.iter() {
+        let stick = Vec2::new(
+            axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickX))
+                .unwrap_or_default(),
+            -axes
+                .get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickY))
+                .unwrap_or_default(),
+        );
+        if stick.length() > GAMEPAD_DEAD_ZONE {
+            rotation += stick * ORBIT_GAMEPAD_SPEED * time.delta_seconds();
+        }
+    }
+    let zoom: f32 = mouse_wheel.iter().map(|wheel| wheel.y).sum::<f32>() * ORBIT_ZOOM_SPEED;
+
+    for mut orbit in &mut orbits {
+        orbit.yaw -= rotation.x;
+        orbit.pitch = (orbit.pitch + rotation.y).clamp(0.1, std::f32::consts::FRAC_PI_2 - 0.01);
+        orbit.radius = (orbit.radius - zoom).clamp(ORBIT_MIN_RADIUS, ORBIT_MAX_RADIUS);
+    }
+}
+
+pub(crate) fn update_camera(
+    config: Res<CameraConfig>,
+    time: Res<Time>,
+    marble: Query<&GlobalTransform, With<Marble>>,
+    mut camera: Query<(&mut Transform, &Orbit), With<GameCamera>>,
+) {
+    for (mut transform, orbit) in &mut camera {
+        match config.mode {
+            CameraMode::Fixed => {
+                *transform =
+                    Transform::from_translation(FIXED_POSITION).looking_at(Vec3::ZERO, Vec3::Y);
+            }
+            CameraMode::Follow => {
+                let Ok(marble) = marble.get_single() else {
+                    continue;
+                };
+                let target = marble.translation();
+                // Exponential smoothing so the camera doesn't shake with the marble
+                let smoothing = 1. - (-FOLLOW_SPEED * time.delta_seconds()).exp();
+                transform.translation = transform
+                    .translation
+                    .lerp(target + FOLLOW_OFFSET, smoothing);
+                transform.look_at(target, Vec3::Y);
+            }
+            CameraMode::TopDown => {
+                // North of the maze is at the top of the screen
+                *transform = Transform::from_xyz(0., TOP_DOWN_HEIGHT, 0.)
+                    .looking_at(Vec3::ZERO, Vec3::NEG_Z);
+            }
+            CameraMode::Orbit => {
+                *transform =
+                    Transform::from_translation(orbit.position()).looking_at(Vec3::ZERO, Vec3::Y);
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Checkpoints
+//!
+//! Cells evenly spread on the way from the start to the goal. Once the
+//! marble went over a checkpoint, it is put back there instead of the
+//! start cell when it is lost.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use maze_generator::prelude::Coordinates;
+
+use crate::resources::{CheckpointConfig, CurrentMaze};
+
+use super::{
+    actions::GameAction,
+    board::{Floor, Marble, OccupiedCells, MARBLE_RADIUS},
+    GameComponent,
+};
+
+const CHECKPOINT_COLOR: Color = Color::rgb(0.3, 0.3, 0.8);
+const REACHED_COLOR: Color = Color::rgb(0.5, 0.5, 1.);
+
+#[derive(Component)]
+pub(crate) struct Checkpoint {
+    /// Where to put the marble back.
+    position: Vec3,
+}
+
+#[derive(Resource)]
+pub(crate) struct CheckpointMaterials {
+    normal: Handle<StandardMaterial>,
+    reached: Handle<StandardMaterial>,
+}
+
+/// Cells of the solution path, excluding start and goal, that are checkpoints.
+fn checkpoint_cells(current_maze: &CurrentMaze, count: usize) -> Vec<Coordinates> {
+    let solution = current_maze.solution();
+    if solution.len() < 3 {
+        return Vec::new();
+    }
+    let mut cells: Vec<Coordinates> = (1..=count)
+        .map(|index| solution[index * (solution.len() - 1) / (count + 1)])
+        .filter(|cell| *cell != current_maze.start() && *cell != current_maze.goal())
+        .collect();
+    cells.dedup();
+    cells
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn setup_checkpoints(
+    mut commands: Commands,
+    config: Res<CheckpointConfig>,
+    current_maze: Res<CurrentMaze>,
+    mut occupied: ResMut<OccupiedCells>,
+    floor: Query<Entity, With<Floor>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !config.enabled {
+        return;
+    }
+    let Ok(floor) = floor.get_single() else {
+        return;
+    };
+
+    let checkpoint_materials = CheckpointMaterials {
+        normal: materials.add(CHECKPOINT_COLOR.into()),
+        reached: materials.add(StandardMaterial {
+            base_color: REACHED_COLOR,
+            emissive: REACHED_COLOR,
+            ..default()
+        }),
+    };
+    let size = current_maze.cell_size() * 0.8;
+    let mesh = meshes.add(Mesh::from(shape::Box::new(size.x, 0.002, size.y)));
+
+    for cell in checkpoint_cells(&current_maze, config.count) {
+        occupied.items.insert(cell);
+        let position = current_maze.cell_center(cell);
+        let checkpoint = commands
+            .spawn(PbrBundle {
+                mesh: mesh.clone(),
+                material: checkpoint_materials.normal.clone(),
+                transform: Transform::from_translation(position + Vec3::Y * 0.001),
+                ..default()
+            })
+            .insert(Collider::cuboid(size.x / 2., 0.005, size.y / 2.))
+            .insert(Sensor)
+            .insert(Checkpoint {
+                position: position + Vec3::Y * (MARBLE_RADIUS + 0.01),
+            })
+            .insert(GameComponent)
+            .id();
+        commands.entity(floor).add_child(checkpoint);
+    }
+
+    commands.insert_resource(checkpoint_materials);
+}
+
+pub(crate) fn detect_checkpoints(
+    mut collision_events: EventReader<CollisionEvent>,
+    materials: Option<Res<CheckpointMaterials>>,
+    mut checkpoints: Query<(&Checkpoint, &mut Handle<StandardMaterial>)>,
+    mut marble: Query<&mut Marble>,
+) {
+    let Some(materials) = materials else {
+        return;
+    };
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(first, second, _) = collision_event {
+            for entity in [first, second] {
+                let Ok((checkpoint, mut material)) = checkpoints.get_mut(*entity) else {
+                    continue;
+                };
+                if let Ok(mut marble) = marble.get_single_mut() {
+                    if marble.checkpoint != Some(checkpoint.position) {
+                        info!("Checkpoint reached");
+                        marble.checkpoint = Some(checkpoint.position);
+                    }
+                }
+                *material = materials.reached.clone();
+            }
+        }
+    }
+}
+
+pub(crate) fn reset_checkpoints(
+    mut actions: EventReader<GameAction>,
+    materials: Option<Res<CheckpointMaterials>>,
+    mut checkpoints: Query<&mut Handle<StandardMaterial>, With<Checkpoint>>,
+    mut marble: Query<&mut Marble>,
+) {
+    if !actions.iter().any(|action| *action == GameAction::Restart) {
+        return;
+    }
+    if let Ok(mut marble) = marble.get_single_mut() {
+        marble.checkpoint = None;
+    }
+    if let Some(materials) = materials {
+        for mut material in &mut checkpoints {
+            *material = materials.normal.clone();
+        }
+    }
+}
+
+pub(crate) fn remove_checkpoint_materials(mut commands: Commands) {
+    commands.remove_resource::<CheckpointMaterials>();
+}
@@ -0,0 +1,152 @@
+//! Co-op mode
+//!
+//! Two players share the board : the first one tilts it around `x` (pitch),
+//! the second one around `z` (roll), each with the controls chosen in the
+//! menu. The run is timed as usual, and results are kept in their own local
+//! leaderboard.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    resources::{CoopConfig, GameMode, MazeConfig},
+    results::Results,
+    storage, AppState,
+};
+
+use super::{
+    board::{self, GoalReached, TiltInput},
+    GameComponent,
+};
+
+const SAVE_KEY: &str = "coop";
+/// Number of results kept in the leaderboard.
+const LEADERBOARD_SIZE: usize = 10;
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct CoopResult {
+    score: u32,
+    time: f32,
+}
+
+/// Saved results of the co-op runs.
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub(crate) struct CoopRecords {
+    /// Best results, best score first.
+    leaderboard: Vec<CoopResult>,
+}
+
+impl CoopRecords {
+    pub(crate) fn load() -> Self {
+        storage::load(SAVE_KEY).unwrap_or_default()
+    }
+
+    pub(crate) fn best(&self) -> Option<u32> {
+        self.leaderboard.first().map(|result| result.score)
+    }
+
+    /// Add a result, return its rank in the leaderboard, if it's in.
+    fn record(&mut self, result: CoopResult) -> Option<usize> {
+        let rank = self
+            .leaderboard
+            .iter()
+            .position(|other| other.score < result.score)
+            .unwrap_or(self.leaderboard.len());
+        self.leaderboard.insert(rank, result);
+        self.leaderboard.truncate(LEADERBOARD_SIZE);
+        (rank < LEADERBOARD_SIZE).then_some(rank)
+    }
+}
+
+/// Configure the game for a co-op run.
+pub(crate) fn start(mode: &mut GameMode, maze_config: &mut MazeConfig) {
+    *mode = GameMode::Coop;
+    *maze_config = MazeConfig::default();
+}
+
+pub(crate) fn is_coop(mode: Res<GameMode>) -> bool {
+    *mode == GameMode::Coop
+}
+
+/// Remind who tilts the board around which axis.
+pub(crate) fn setup_coop(mut commands: Commands, config: Res<CoopConfig>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(GameComponent)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!(
+                    "Pitch : {} - Roll : {}",
+                    config.controls[0].label(),
+                    config.controls[1].label()
+                ),
+                TextStyle {
+                    font_size: 25.0,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// Replace the player input by the axis each player controls.
+pub(crate) fn handle_input(
+    keyboard: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    config: Res<CoopConfig>,
+    mut tilt: ResMut<TiltInput>,
+) {
+    let [pitch, roll] = config
+        .controls
+        .map(|controls| board::controls_input(&[controls], &keyboard, &gamepads, &axes));
+    *tilt = TiltInput {
+        x: pitch.x,
+        z: roll.z,
+    };
+}
+
+pub(crate) fn finish_coop(
+    mut commands: Commands,
+    mut reached: EventReader<GoalReached>,
+    mode: Res<GameMode>,
+    mut records: ResMut<CoopRecords>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    for goal in reached.iter() {
+        if *mode != GameMode::Coop {
+            continue;
+        }
+        let rank = records.record(CoopResult {
+            score: goal.score,
+            time: goal.time,
+        });
+        storage::save(SAVE_KEY, &*records);
+
+        let mut lines = vec![match rank {
+            Some(rank) => format!("Rank {} in the leaderboard", rank + 1),
+            None => "Not in the leaderboard".to_string(),
+        }];
+        lines.extend(
+            records
+                .leaderboard
+                .iter()
+                .enumerate()
+                .map(|(index, result)| {
+                    format!("{}. {} in {:.2}s", index + 1, result.score, result.time)
+                }),
+        );
+        commands.insert_resource(Results::new("Co-op", lines));
+        state.set(AppState::Results);
+    }
+}
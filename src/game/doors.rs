@@ -0,0 +1,249 @@
+//! Keys and locked doors
+//!
+//! Some passages of the solution path are closed by doors. Each door opens
+//! once the marble picked up the key of the same color. Keys are placed so
+//! they can be reached without going through their door (nor any hole), so
+//! the maze can always be solved.
+
+use std::{collections::HashSet, f32::consts::PI};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use maze_generator::prelude::Coordinates;
+use rand::seq::SliceRandom;
+
+use crate::resources::{CurrentMaze, DoorConfig};
+
+use super::{
+    actions::GameAction,
+    board::{Floor, OccupiedCells, Spinning, BORDER_HEIGHT, MARBLE_RADIUS},
+    GameComponent,
+};
+
+const KEY_SALT: u64 = 0x6b657973;
+const KEY_SPINNING_SPEED: f32 = 2.;
+const DOOR_THICKNESS: f32 = 0.02;
+const COLORS: [Color; 4] = [Color::RED, Color::YELLOW, Color::FUCHSIA, Color::ORANGE];
+
+/// A door, opened by the [Key] with the same number.
+#[derive(Component)]
+pub(crate) struct Door(usize);
+
+#[derive(Component)]
+pub(crate) struct Key(usize);
+
+struct DoorPlacement {
+    /// The passage closed by the door.
+    passage: (Coordinates, Coordinates),
+    key: Coordinates,
+}
+
+/// Place doors on the solution path, each key in the part of the maze that
+/// can be reached once the previous doors are opened.
+fn place_doors(
+    current_maze: &CurrentMaze,
+    count: usize,
+    occupied: &OccupiedCells,
+) -> Vec<DoorPlacement> {
+    let path = current_maze.solution();
+    if path.len() < 2 {
+        return Vec::new();
+    }
+
+    let on_path: HashSet<Coordinates> = path.iter().copied().collect();
+    let mut rng = current_maze.rng(KEY_SALT);
+    let mut taken = occupied.items.clone();
+    let mut previous_region = HashSet::new();
+    let mut previous_index = 0;
+    let mut placements = Vec::new();
+    for door in 1..=count.min(COLORS.len()) {
+        let index = door * (path.len() - 1) / (count + 1);
+        if index <= previous_index && door > 1 {
+            continue;
+        }
+        previous_index = index;
+
+        let passage = (path[index], path[index + 1]);
+        let region = current_maze.reachable(current_maze.start(), &occupied.holes, &[passage]);
+        let mut candidates: Vec<Coordinates> = region
+            .iter()
+            .filter(|cell| !taken.contains(*cell) && **cell != current_maze.start())
+            .copied()
+            .collect();
+        // Sets aren't ordered, sort them so a maze always gets the same keys
+        candidates.sort_by_key(|cell| (cell.y, cell.x));
+        // Rather put keys off the path, in the part that has just been opened
+        let preferred: Vec<Coordinates> = candidates
+            .iter()
+            .filter(|cell| !on_path.contains(*cell) && !previous_region.contains(*cell))
+            .copied()
+            .collect();
+        let Some(key) = preferred
+            .choose(&mut rng)
+            .or_else(|| candidates.choose(&mut rng))
+            .copied()
+        else {
+            continue;
+        };
+
+        taken.insert(key);
+        placements.push(DoorPlacement { passage, key });
+        previous_region = region;
+    }
+
+    placements
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn setup_doors(
+    mut commands: Commands,
+    config: Res<DoorConfig>,
+    current_maze: Res<CurrentMaze>,
+    mut occupied: ResMut<OccupiedCells>,
+    floor: Query<Entity, With<Floor>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !config.enabled {
+        return;
+    }
+    let Ok(floor) = floor.get_single() else {
+        return;
+    };
+
+    let cell_size = current_maze.cell_size();
+    let key_size = cell_size.min_element() * 0.2;
+    let key_mesh = meshes.add(Mesh::from(shape::Torus {
+        radius: key_size,
+        ring_radius: key_size / 3.,
+        ..default()
+    }));
+
+    for (number, placement) in place_doors(&current_maze, config.count, &occupied)
+        .into_iter()
+        .enumerate()
+    {
+        occupied.items.insert(placement.key);
+        let material = materials.add(COLORS[number].into());
+
+        // Door, between the two cells of the passage
+        let (first, second) = placement.passage;
+        let (length, rotation) = if first.y == second.y {
+            (cell_size.y, Quat::from_rotation_y(PI / 2.))
+        } else {
+            (cell_size.x, Quat::IDENTITY)
+        };
+        let position = (current_maze.cell_center(first) + current_maze.cell_center(second)) / 2.
+            + Vec3::Y * BORDER_HEIGHT / 2.;
+        let door = commands
+            .spawn(PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::Box::new(
+                    length,
+                    BORDER_HEIGHT,
+                    DOOR_THICKNESS,
+                ))),
+                material: material.clone(),
+                transform: Transform::from_translation(position).with_rotation(rotation),
+                ..default()
+            })
+            .insert(RigidBody::Fixed)
+            .insert(Collider::cuboid(
+                length / 2.,
+                BORDER_HEIGHT / 2.,
+                DOOR_THICKNESS / 2.,
+            ))
+            .insert(Door(number))
+            .insert(GameComponent)
+            .id();
+
+        // Key, standing in its cell
+        let key = commands
+            .spawn(PbrBundle {
+                mesh: key_mesh.clone(),
+                material,
+                transform: Transform::from_translation(
+                    current_maze.cell_center(placement.key) + Vec3::Y * MARBLE_RADIUS,
+                )
+                .with_rotation(Quat::from_rotation_x(PI / 2.)),
+                ..default()
+            })
+            .insert(Collider::ball(key_size))
+            .insert(Sensor)
+            .insert(Key(number))
+            .insert(Spinning(KEY_SPINNING_SPEED))
+            .insert(GameComponent)
+            .id();
+
+        commands.entity(floor).push_children(&[door, key]);
+    }
+}
+
+pub(crate) fn pick_keys(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    keys: Query<&Key, Without<ColliderDisabled>>,
+    doors: Query<(Entity, &Door)>,
+) {
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(first, second, _) = collision_event {
+            for entity in [first, second] {
+                let Ok(Key(number)) = keys.get(*entity) else {
+                    continue;
+                };
+                info!("Key {number} picked up");
+                // Hide instead of despawning, so they can come back on restart
+                commands
+                    .entity(*entity)
+                    .insert(Visibility::Hidden)
+                    .insert(ColliderDisabled);
+                for (door, Door(door_number)) in &doors {
+                    if door_number == number {
+                        commands
+                            .entity(door)
+                            .insert(Visibility::Hidden)
+                            .insert(ColliderDisabled);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn reset_doors(
+    mut commands: Commands,
+    mut actions: EventReader<GameAction>,
+    elements: Query<Entity, Or<(With<Key>, With<Door>)>>,
+) {
+    if !actions.iter().any(|action| *action == GameAction::Restart) {
+        return;
+    }
+    for entity in &elements {
+        commands
+            .entity(entity)
+            .insert(Visibility::Inherited)
+            .remove::<ColliderDisabled>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_before_their_door() {
+        for seed in 0..20 {
+            let current_maze = CurrentMaze::seeded(seed);
+            let occupied = OccupiedCells::default();
+            let placements = place_doors(&current_maze, COLORS.len(), &occupied);
+            for (index, placement) in placements.iter().enumerate() {
+                // Previous doors are open, this one and the next ones closed
+                let closed: Vec<(Coordinates, Coordinates)> = placements[index..]
+                    .iter()
+                    .map(|placement| placement.passage)
+                    .collect();
+                let region = current_maze.reachable(current_maze.start(), &occupied.holes, &closed);
+                assert!(region.contains(&placement.key));
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Endless mode
+//!
+//! Mazes are chained : reaching the goal directly generates the next one,
+//! a bit bigger and with another algorithm. Scores and times add up until
+//! the player goes back to the menu or runs out of lives.
+
+use bevy::prelude::*;
+
+use crate::{
+    resources::{Algorithm, GameMode, MazeConfig},
+    AppState,
+};
+
+use super::board::GoalReached;
+
+/// Size of the first maze.
+const START_SIZE: i32 = 5;
+const MAX_SIZE: i32 = 25;
+const ALGORITHMS: [Algorithm; 4] = [
+    Algorithm::RecursiveBacktracking,
+    Algorithm::GrowingTree,
+    Algorithm::Prims,
+    Algorithm::Ellers,
+];
+
+/// Progress of the current endless run.
+#[derive(Resource, Default)]
+pub(crate) struct EndlessRun {
+    pub(crate) score: u32,
+    /// Seconds spent in the finished mazes.
+    pub(crate) total_time: f32,
+}
+
+#[derive(Component)]
+pub(crate) struct EndlessText;
+
+/// Set the maze of the current stage, before it is generated.
+pub(crate) fn configure_stage(
+    mode: Res<GameMode>,
+    mut maze_config: ResMut<MazeConfig>,
+    mut run: ResMut<EndlessRun>,
+) {
+    let GameMode::Endless { stage } = *mode else {
+        return;
+    };
+    if stage == 0 {
+        *run = EndlessRun::default();
+    }
+    let size = (START_SIZE + stage as i32).min(MAX_SIZE);
+    maze_config.set_width(size);
+    maze_config.set_height(size);
+    maze_config.set_algorithm(ALGORITHMS[stage as usize % ALGORITHMS.len()]);
+    maze_config.set_seed(None);
+}
+
+pub(crate) fn next_stage(
+    mut reached: EventReader<GoalReached>,
+    mut mode: ResMut<GameMode>,
+    mut run: ResMut<EndlessRun>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    for goal in reached.iter() {
+        let GameMode::Endless { stage } = *mode else {
+            continue;
+        };
+        run.score += goal.score;
+        run.total_time += goal.time;
+        info!(
+            "Maze {} done, score {} in {:.2}s",
+            stage + 1,
+            run.score,
+            run.total_time
+        );
+        *mode = GameMode::Endless { stage: stage + 1 };
+        state.set(AppState::Loading);
+    }
+}
+
+/// Log the result of the run once back to the menu.
+pub(crate) fn end_run(mut mode: ResMut<GameMode>, run: Res<EndlessRun>) {
+    let GameMode::Endless { stage } = *mode else {
+        return;
+    };
+    info!(
+        "Endless run over : {} mazes, score {} in {:.2}s",
+        stage, run.score, run.total_time
+    );
+    *mode = GameMode::Free;
+}
+
+pub(crate) fn update_endless_text(
+    mode: Res<GameMode>,
+    run: Res<EndlessRun>,
+    mut text: Query<&mut Text, With<EndlessText>>,
+) {
+    let GameMode::Endless { stage } = *mode else {
+        return;
+    };
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = format!(
+            "Maze {} - Score : {} - Total : {:.2}",
+            stage + 1,
+            run.score,
+            run.total_time
+        );
+    }
+}
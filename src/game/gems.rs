@@ -0,0 +1,149 @@
+//! Gems mode
+//!
+//! Gems are scattered in dead ends of the maze, never behind a hole. The
+//! goal is only accepted once enough of them have been collected, and
+//! collecting all of them gives a score bonus (see [GemConfig]).
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use maze_generator::prelude::Coordinates;
+use rand::seq::SliceRandom;
+
+use crate::resources::{CurrentMaze, GemConfig};
+
+use super::{
+    actions::GameAction,
+    board::{Floor, OccupiedCells, Spinning, MARBLE_RADIUS},
+    GameComponent,
+};
+
+const GEM_SALT: u64 = 0x67656d73;
+const GEM_COLOR: Color = Color::rgb(0., 0.8, 0.9);
+const GEM_SPINNING_SPEED: f32 = 2.;
+
+#[derive(Component)]
+pub(crate) struct Gem;
+
+/// Gems progress of the current run.
+#[derive(Resource, Default)]
+pub(crate) struct Gems {
+    pub(crate) collected: u32,
+    pub(crate) required: u32,
+    pub(crate) total: u32,
+}
+
+#[derive(Component)]
+pub(crate) struct GemsText;
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn setup_gems(
+    mut commands: Commands,
+    config: Res<GemConfig>,
+    current_maze: Res<CurrentMaze>,
+    mut occupied: ResMut<OccupiedCells>,
+    floor: Query<Entity, With<Floor>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(Gems::default());
+    if !config.enabled {
+        return;
+    }
+    let Ok(floor) = floor.get_single() else {
+        return;
+    };
+
+    let reachable = current_maze.reachable(current_maze.start(), &occupied.holes, &[]);
+    let mut cells: Vec<Coordinates> = current_maze
+        .dead_ends()
+        .into_iter()
+        .filter(|cell| reachable.contains(cell) && !occupied.items.contains(cell))
+        .collect();
+    cells.shuffle(&mut current_maze.rng(GEM_SALT));
+    cells.truncate(config.count as usize);
+    occupied.items.extend(cells.iter().copied());
+
+    let total = cells.len() as u32;
+    commands.insert_resource(Gems {
+        collected: 0,
+        required: config.required.unwrap_or(total).min(total),
+        total,
+    });
+
+    let size = current_maze.cell_size().min_element() * 0.3;
+    let mesh = meshes.add(Mesh::from(shape::Cube { size }));
+    let material = materials.add(StandardMaterial {
+        base_color: GEM_COLOR,
+        emissive: GEM_COLOR * 0.3,
+        ..default()
+    });
+    for cell in cells {
+        let gem = commands
+            .spawn(PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_translation(
+                    current_maze.cell_center(cell) + Vec3::Y * MARBLE_RADIUS,
+                )
+                .with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_4)),
+                ..default()
+            })
+            .insert(Collider::ball(size / 2.))
+            .insert(Sensor)
+            .insert(Gem)
+            .insert(Spinning(GEM_SPINNING_SPEED))
+            .insert(GameComponent)
+            .id();
+        commands.entity(floor).add_child(gem);
+    }
+}
+
+pub(crate) fn collect_gems(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    gem_entities: Query<(), (With<Gem>, Without<ColliderDisabled>)>,
+    mut gems: ResMut<Gems>,
+) {
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(first, second, _) = collision_event {
+            for entity in [first, second] {
+                if gem_entities.contains(*entity) {
+                    // Hide it instead of despawning it, so it can come back on restart
+                    commands
+                        .entity(*entity)
+                        .insert(Visibility::Hidden)
+                        .insert(ColliderDisabled);
+                    gems.collected += 1;
+                    info!("Gem collected ({}/{})", gems.collected, gems.total);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn reset_gems(
+    mut commands: Commands,
+    mut actions: EventReader<GameAction>,
+    gem_entities: Query<Entity, With<Gem>>,
+    mut gems: ResMut<Gems>,
+) {
+    if !actions.iter().any(|action| *action == GameAction::Restart) {
+        return;
+    }
+    gems.collected = 0;
+    for entity in &gem_entities {
+        commands
+            .entity(entity)
+            .insert(Visibility::Inherited)
+            .remove::<ColliderDisabled>();
+    }
+}
+
+pub(crate) fn update_gems_text(gems: Res<Gems>, mut text: Query<&mut Text, With<GemsText>>) {
+    if gems.total == 0 || !gems.is_changed() {
+        return;
+    }
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = format!("Gems : {}/{}", gems.collected, gems.total);
+    }
+}
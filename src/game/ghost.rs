@@ -0,0 +1,280 @@
+//! Ghost marble
+//!
+//! The position of the marble is recorded at each physics step. The fastest
+//! run of a maze, with the same mechanics, is saved and replayed by a
+//! translucent marble without collider, so the player can race against it.
+//! Only mazes with a configured seed (campaign levels, daily challenges...)
+//! get a ghost, random ones are never played twice.
+//!
+//! Ghosts are saved in their own files, which can be shared : dropping a
+//! ghost file on the window from the menu starts its maze with this ghost.
+//! The imported ghost is forgotten when back in the menu, when the maze
+//! changes or when the player hides it. Browsers don't give the window the
+//! path of dropped files, so ghosts can only be imported natively.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    options::{GameOption, GameOptions},
+    resources::{CurrentMaze, GameMode, MazeConfig},
+    storage, AppState,
+};
+
+use super::{
+    actions::GameAction,
+    board::{Floor, GoalReached, Marble, Step, MARBLE_RADIUS},
+    GameComponent,
+};
+
+/// A run that reached the goal.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Ghost {
+    maze: MazeConfig,
+    mechanics: Vec<GameOption>,
+    /// Seconds it took, penalties included.
+    time: f32,
+    /// Position of the marble in the floor space at the beginning of each
+    /// physics step.
+    positions: Vec<(f32, f32, f32)>,
+}
+
+impl Ghost {
+    fn is_for(&self, maze: &MazeConfig, mechanics: &[GameOption]) -> bool {
+        self.maze == *maze && self.mechanics == mechanics
+    }
+}
+
+/// Storage key of the ghost of a maze played with `mechanics`.
+fn save_key(maze: &MazeConfig, mechanics: &[GameOption]) -> String {
+    let flags = mechanics.iter().fold(0u32, |flags, mechanic| {
+        let index = GameOption::ALL.iter().position(|option| option == mechanic);
+        flags | index.map_or(0, |index| 1 << index)
+    });
+    format!("ghost-{}-{flags:x}", maze.id())
+}
+
+/// The ghost shared by someone else, used instead of the player's one
+/// while its maze is played.
+#[derive(Resource)]
+pub(crate) struct ImportedGhost(Ghost);
+
+/// The ghost raced in the current game.
+#[derive(Resource)]
+pub(crate) struct GhostRun(Vec<Vec3>);
+
+/// Positions of the marble during the current run.
+#[derive(Resource, Default)]
+pub(crate) struct Trajectory(Vec<Vec3>);
+
+#[derive(Component)]
+pub(crate) struct GhostMarble;
+
+pub(crate) fn setup_ghost(
+    mut commands: Commands,
+    current_maze: Res<CurrentMaze>,
+    options: GameOptions,
+    imported: Option<Res<ImportedGhost>>,
+    floor: Query<Entity, With<Floor>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(Trajectory::default());
+    commands.remove_resource::<GhostRun>();
+    let Ok(floor) = floor.get_single() else {
+        return;
+    };
+
+    let maze = current_maze.config();
+    let mechanics = options.enabled_options();
+    // An imported ghost is only raced on its own maze
+    let imported = match imported {
+        Some(imported) if imported.0.is_for(maze, &mechanics) => Some(imported),
+        Some(_) => {
+            info!("Maze changed, imported ghost dropped");
+            commands.remove_resource::<ImportedGhost>();
+            None
+        }
+        None => None,
+    };
+    let saved = storage::load::<Ghost>(&save_key(maze, &mechanics));
+    let ghost = match &imported {
+        Some(imported) => &imported.0,
+        None => match &saved {
+            Some(saved) if saved.is_for(maze, &mechanics) => saved,
+            _ => return,
+        },
+    };
+    info!("Racing a ghost of {:.2}s", ghost.time);
+    commands.insert_resource(GhostRun(
+        ghost
+            .positions
+            .iter()
+            .map(|(x, y, z)| Vec3::new(*x, *y, *z))
+            .collect(),
+    ));
+
+    let ghost = commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(
+                Mesh::try_from(shape::Icosphere {
+                    radius: MARBLE_RADIUS,
+                    subdivisions: 5,
+                })
+                .unwrap(),
+            ),
+            material: materials.add(Color::rgba(0.8, 0.8, 1., 0.35).into()),
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert(GhostMarble)
+        .insert(GameComponent)
+        .id();
+    commands.entity(floor).add_child(ghost);
+}
+
+pub(crate) fn record_trajectory(
+    step: Res<Step>,
+    trajectory: Option<ResMut<Trajectory>>,
+    floor: Query<&Transform, (With<Floor>, Without<Marble>)>,
+    marble: Query<&Transform, (With<Marble>, Without<Floor>)>,
+) {
+    let (Some(mut trajectory), Ok(floor), Ok(marble)) =
+        (trajectory, floor.get_single(), marble.get_single())
+    else {
+        return;
+    };
+    // The run has been restarted
+    if step.0 == 0 {
+        trajectory.0.clear();
+    }
+    trajectory.0.push(
+        floor
+            .compute_affine()
+            .inverse()
+            .transform_point3(marble.translation),
+    );
+}
+
+pub(crate) fn move_ghost(
+    step: Res<Step>,
+    ghost_run: Option<Res<GhostRun>>,
+    mut ghost: Query<(&mut Transform, &mut Visibility), With<GhostMarble>>,
+) {
+    let (Some(ghost_run), Ok((mut transform, mut visibility))) =
+        (ghost_run, ghost.get_single_mut())
+    else {
+        return;
+    };
+    // The ghost disappears once it reached the goal
+    match ghost_run.0.get(step.0 as usize) {
+        Some(position) => {
+            transform.translation = *position;
+            *visibility = Visibility::Inherited;
+        }
+        None => *visibility = Visibility::Hidden,
+    }
+}
+
+/// Stop racing the ghost until the next maze, forgetting the imported one.
+pub(crate) fn hide_ghost(
+    mut commands: Commands,
+    mut actions: EventReader<GameAction>,
+    ghost: Query<Entity, With<GhostMarble>>,
+) {
+    if !actions
+        .iter()
+        .any(|action| *action == GameAction::HideGhost)
+    {
+        return;
+    }
+    info!("Ghost hidden");
+    commands.remove_resource::<GhostRun>();
+    commands.remove_resource::<ImportedGhost>();
+    for entity in &ghost {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// The imported ghost was raced, the next games use the player's ones.
+pub(crate) fn forget_imported_ghost(mut commands: Commands) {
+    commands.remove_resource::<ImportedGhost>();
+}
+
+/// Save the run if it is the fastest one.
+pub(crate) fn save_ghost(
+    mut reached: EventReader<GoalReached>,
+    mode: Res<GameMode>,
+    maze_config: Res<MazeConfig>,
+    current_maze: Res<CurrentMaze>,
+    options: GameOptions,
+    trajectory: Option<Res<Trajectory>>,
+) {
+    for goal in reached.iter() {
+        let Some(trajectory) = &trajectory else {
+            continue;
+        };
+        // Each random maze would get its own file, kept forever
+        if *mode == GameMode::Replay || !maze_config.is_seeded() {
+            continue;
+        }
+        let maze = current_maze.config();
+        let mechanics = options.enabled_options();
+        let key = save_key(maze, &mechanics);
+        let best = storage::load::<Ghost>(&key).map(|ghost| ghost.time);
+        if matches!(best, Some(best) if best <= goal.time) {
+            continue;
+        }
+
+        info!("New best time, ghost saved");
+        storage::save(
+            &key,
+            &Ghost {
+                maze: *maze,
+                mechanics,
+                time: goal.time,
+                positions: trajectory
+                    .0
+                    .iter()
+                    .map(|position| (position.x, position.y, position.z))
+                    .collect(),
+            },
+        );
+    }
+}
+
+/// Load a ghost file dropped on the window and start its maze.
+pub(crate) fn import_ghost(
+    mut commands: Commands,
+    mut dropped: EventReader<FileDragAndDrop>,
+    mut mode: ResMut<GameMode>,
+    mut maze_config: ResMut<MazeConfig>,
+    mut options: GameOptions,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    for event in dropped.iter() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = event else {
+            continue;
+        };
+        let ghost = match std::fs::read_to_string(path_buf)
+            .map_err(|err| err.to_string())
+            .and_then(|content| ron::from_str::<Ghost>(&content).map_err(|err| err.to_string()))
+        {
+            Ok(ghost) => ghost,
+            Err(err) => {
+                warn!("Can't import ghost {} : {err}", path_buf.display());
+                continue;
+            }
+        };
+
+        info!("Ghost imported from {}", path_buf.display());
+        *mode = GameMode::Free;
+        *maze_config = ghost.maze;
+        options.keep_user_options();
+        for option in GameOption::ALL {
+            options.set(option, ghost.mechanics.contains(&option));
+        }
+        commands.insert_resource(ImportedGhost(ghost));
+        state.set(AppState::Game);
+    }
+}
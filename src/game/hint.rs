@@ -0,0 +1,147 @@
+//! Solution hint
+//!
+//! On demand, light up the next cells of the shortest path from the marble
+//! to the goal. Hints are limited and each one adds a time penalty, see
+//! [HintConfig]. Hints are asked with [UseHint], so replays can use them
+//! again at the same time.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::resources::{CurrentMaze, HintConfig};
+
+use super::{
+    actions::GameAction,
+    board::{Floor, Marble},
+    hud::{GameTimer, RunStats},
+    GameComponent,
+};
+
+const HINT_COLOR: Color = Color::rgb(1., 0.8, 0.);
+
+/// Number of hints the player can still use.
+#[derive(Resource, Default)]
+pub(crate) struct HintsLeft(pub(crate) u32);
+
+/// A floor marker showing the way, removed when its timer ends.
+#[derive(Component)]
+pub(crate) struct HintMarker(Timer);
+
+#[derive(Component)]
+pub(crate) struct HintText;
+
+/// Sent to use a hint, by the player or by the replay watched, see
+/// [super::replay].
+#[derive(Event)]
+pub(crate) struct UseHint;
+
+pub(crate) fn setup_hints(mut commands: Commands, config: Res<HintConfig>) {
+    commands.insert_resource(HintsLeft(config.budget));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handle_hint_actions(
+    mut commands: Commands,
+    mut actions: EventReader<GameAction>,
+    mut hints: EventReader<UseHint>,
+    config: Res<HintConfig>,
+    current_maze: Res<CurrentMaze>,
+    mut hints_left: ResMut<HintsLeft>,
+    mut timer: ResMut<GameTimer>,
+    mut stats: ResMut<RunStats>,
+    floor: Query<(Entity, &GlobalTransform), With<Floor>>,
+    marble: Query<&GlobalTransform, With<Marble>>,
+    markers: Query<Entity, With<HintMarker>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for action in actions.iter() {
+        if *action == GameAction::Restart {
+            hints_left.0 = config.budget;
+            for marker in &markers {
+                commands.entity(marker).despawn_recursive();
+            }
+        }
+    }
+
+    for _ in hints.iter() {
+        if hints_left.0 == 0 {
+            info!("No hint left");
+            continue;
+        }
+        let (Ok((floor, floor_transform)), Ok(marble)) = (floor.get_single(), marble.get_single())
+        else {
+            continue;
+        };
+
+        let from = current_maze
+            .cell_at_world(floor_transform, marble.translation())
+            .unwrap_or_else(|| current_maze.start());
+        let mut path = current_maze.path(from, current_maze.goal());
+        if let Some(length) = config.length {
+            path.truncate(length);
+        }
+
+        hints_left.0 -= 1;
+        stats.hints_used += 1;
+        let elapsed = timer.elapsed() + Duration::from_secs_f32(config.penalty);
+        timer.set_elapsed(elapsed);
+        info!("Hint used, {} left", hints_left.0);
+
+        // Only one hint at a time
+        for marker in &markers {
+            commands.entity(marker).despawn_recursive();
+        }
+
+        let size = current_maze.cell_size().min_element() * 0.4;
+        let mesh = meshes.add(Mesh::from(shape::Plane::from_size(size)));
+        let material = materials.add(StandardMaterial {
+            base_color: HINT_COLOR,
+            emissive: HINT_COLOR,
+            ..default()
+        });
+        for cell in path {
+            let marker = commands
+                .spawn(PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(
+                        current_maze.cell_center(cell) + Vec3::Y * 0.002,
+                    ),
+                    ..default()
+                })
+                .insert(GameComponent)
+                .insert(HintMarker(Timer::from_seconds(
+                    config.duration,
+                    TimerMode::Once,
+                )))
+                .id();
+            commands.entity(floor).add_child(marker);
+        }
+    }
+}
+
+pub(crate) fn remove_expired_hints(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut markers: Query<(Entity, &mut HintMarker)>,
+) {
+    for (entity, mut marker) in &mut markers {
+        if marker.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub(crate) fn update_hint_text(
+    hints_left: Res<HintsLeft>,
+    mut text: Query<&mut Text, With<HintText>>,
+) {
+    if !hints_left.is_changed() {
+        return;
+    }
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = format!("Hints : {}", hints_left.0);
+    }
+}
@@ -0,0 +1,203 @@
+//! Holes mode
+//!
+//! Like the real wooden labyrinth, some cells have a hole the marble can
+//! fall into. Holes are placed in dead ends and next to the solution path,
+//! never on it. Each fall costs a life and puts the marble back on the board,
+//! the game is over when there is no life left.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use maze_generator::prelude::Coordinates;
+use rand::seq::SliceRandom;
+
+use crate::{
+    resources::{CurrentMaze, GameMode, HoleConfig},
+    results::Results,
+    AppState,
+};
+
+use super::{
+    actions::GameAction,
+    board::{Floor, MarbleLost, OccupiedCells, MARBLE_RADIUS},
+    hud::{GameTimer, RunStats},
+    GameComponent,
+};
+
+const HOLE_SALT: u64 = 0x686f6c65;
+
+/// Sensor inside a hole.
+#[derive(Component)]
+pub(crate) struct Hole;
+
+/// Number of falls left before the game is over.
+#[derive(Resource, Default)]
+pub(crate) struct Lives(pub(crate) u32);
+
+#[derive(Component)]
+pub(crate) struct LivesText;
+
+/// Cells with a hole : a `density` ratio of dead ends and cells next to the
+/// solution path, excluding the path itself.
+fn hole_cells(current_maze: &CurrentMaze, density: f32) -> Vec<Coordinates> {
+    let solution: HashSet<Coordinates> = current_maze.solution().into_iter().collect();
+    let mut candidates: Vec<Coordinates> = current_maze
+        .cells()
+        .filter(|cell| !solution.contains(cell))
+        .filter(|cell| {
+            let neighbours = current_maze.neighbours(*cell);
+            neighbours.len() == 1
+                || neighbours
+                    .iter()
+                    .any(|neighbour| solution.contains(neighbour))
+        })
+        .collect();
+
+    candidates.shuffle(&mut current_maze.rng(HOLE_SALT));
+    candidates.truncate((candidates.len() as f32 * density).round() as usize);
+    candidates
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn setup_holes(
+    mut commands: Commands,
+    config: Res<HoleConfig>,
+    current_maze: Res<CurrentMaze>,
+    mut occupied: ResMut<OccupiedCells>,
+    floor: Query<Entity, With<Floor>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mode: Res<GameMode>,
+    mut lives: ResMut<Lives>,
+) {
+    // Lives are carried from one maze to the next in endless mode
+    if matches!(*mode, GameMode::Endless { stage } if stage > 0) {
+        lives.set_changed();
+    } else {
+        lives.0 = config.lives;
+    }
+    if !config.enabled {
+        return;
+    }
+    let Ok(floor) = floor.get_single() else {
+        return;
+    };
+
+    let radius = current_maze.cell_size().min_element() * 0.35;
+    // The marble falls once its center is above the hole
+    let sensor_radius = (radius - MARBLE_RADIUS / 2.).max(0.01);
+    let mesh = meshes.add(Mesh::from(shape::Cylinder {
+        radius,
+        height: 0.002,
+        ..default()
+    }));
+    let material = materials.add(Color::BLACK.into());
+
+    for cell in hole_cells(&current_maze, config.density) {
+        occupied.holes.insert(cell);
+        let position = current_maze.cell_center(cell);
+        let hole = commands
+            .spawn(PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_translation(position + Vec3::Y * 0.001),
+                ..default()
+            })
+            .insert(GameComponent)
+            .id();
+        let sensor = commands
+            .spawn(Collider::cylinder(0.005, sensor_radius))
+            .insert(Sensor)
+            .insert(Hole)
+            .insert(TransformBundle::from_transform(
+                Transform::from_translation(position + Vec3::Y * 0.005),
+            ))
+            .insert(GameComponent)
+            .id();
+        commands.entity(floor).push_children(&[hole, sensor]);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn detect_fall(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    holes: Query<(), With<Hole>>,
+    mut lives: ResMut<Lives>,
+    mut stats: ResMut<RunStats>,
+    timer: Res<GameTimer>,
+    mut lost: EventWriter<MarbleLost>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(first, second, _) = collision_event {
+            if !holes.contains(*first) && !holes.contains(*second) {
+                continue;
+            }
+
+            stats.falls += 1;
+            lives.0 = lives.0.saturating_sub(1);
+            if lives.0 == 0 {
+                info!("Fell in a hole, game over");
+                commands.insert_resource(
+                    Results::new(
+                        "Game over",
+                        vec![
+                            "No life left".to_string(),
+                            format!("Time : {:.2}s", timer.elapsed_secs()),
+                            format!("Falls : {}", stats.falls),
+                        ],
+                    )
+                    .without_replay(),
+                );
+                state.set(AppState::Results);
+            } else {
+                info!("Fell in a hole, {} lives left", lives.0);
+                lost.send(MarbleLost);
+            }
+        }
+    }
+}
+
+pub(crate) fn reset_lives(
+    mut actions: EventReader<GameAction>,
+    config: Res<HoleConfig>,
+    mode: Res<GameMode>,
+    mut lives: ResMut<Lives>,
+) {
+    // Restarting a maze doesn't give lives back during an endless run
+    if actions.iter().any(|action| *action == GameAction::Restart)
+        && !matches!(*mode, GameMode::Endless { .. })
+    {
+        lives.0 = config.lives;
+    }
+}
+
+pub(crate) fn update_lives_text(
+    config: Res<HoleConfig>,
+    lives: Res<Lives>,
+    mut text: Query<&mut Text, With<LivesText>>,
+) {
+    if !config.enabled || !lives.is_changed() {
+        return;
+    }
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = format!("Lives : {}", lives.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holes_are_off_the_solution() {
+        for seed in 0..20 {
+            let current_maze = CurrentMaze::seeded(seed);
+            let solution = current_maze.solution();
+            let holes = hole_cells(&current_maze, 1.);
+            assert!(holes.iter().all(|hole| !solution.contains(hole)));
+        }
+    }
+}
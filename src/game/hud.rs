@@ -0,0 +1,175 @@
+//! In game interface
+//!
+//! Display elapsed time, hints and lives left, gems progress, active
+//! power-ups, endless run progress and buttons for [GameAction].
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::Stopwatch};
+
+use crate::resources::MazeConfig;
+
+use super::{
+    actions::GameAction, board::STEP, endless::EndlessText, gems::GemsText, hint::HintText,
+    holes::LivesText, powerups::PowerUpText, GameComponent,
+};
+
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const NORMAL_BUTTON: Color = Color::rgb(0.3, 0.3, 0.3);
+
+/// Time elapsed since the beginning of the current run.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub(crate) struct GameTimer(Stopwatch);
+
+/// Score when the goal is reached instantly.
+const BASE_SCORE: f32 = 10000.;
+/// Points lost each second.
+const POINTS_PER_SECOND: f32 = 20.;
+
+/// Statistics of the current run.
+#[derive(Resource, Default)]
+pub(crate) struct RunStats {
+    /// Number of times the marble escaped the board.
+    pub(crate) faults: u32,
+    /// Number of times the marble fell in a hole.
+    pub(crate) falls: u32,
+    pub(crate) hints_used: u32,
+    /// Points added to the score, for example when all gems are collected.
+    pub(crate) bonus: u32,
+}
+
+impl RunStats {
+    /// Score of the run when the goal is reached after `elapsed_secs`.
+    pub(crate) fn score(&self, elapsed_secs: f32) -> u32 {
+        (BASE_SCORE - elapsed_secs * POINTS_PER_SECOND).max(0.) as u32 + self.bonus
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct TimerText;
+
+pub(crate) fn setup_hud(mut commands: Commands, maze_config: Res<MazeConfig>) {
+    commands.insert_resource(GameTimer::default());
+    commands.insert_resource(RunStats::default());
+
+    let button_style = Style {
+        width: Val::Px(150.0),
+        height: Val::Px(40.0),
+        margin: UiRect::all(Val::Px(5.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let text_style = TextStyle {
+        font_size: 25.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            GameComponent,
+        ))
+        .with_children(|parent| {
+            parent.spawn(NodeBundle::default()).with_children(|parent| {
+                parent.spawn((
+                    TextBundle::from_section("0.00", text_style.clone()).with_style(Style {
+                        margin: UiRect::right(Val::Px(20.0)),
+                        ..default()
+                    }),
+                    TimerText,
+                ));
+                parent.spawn((
+                    TextBundle::from_section("", text_style.clone()).with_style(Style {
+                        margin: UiRect::right(Val::Px(20.0)),
+                        ..default()
+                    }),
+                    HintText,
+                ));
+                parent.spawn((
+                    TextBundle::from_section("", text_style.clone()).with_style(Style {
+                        margin: UiRect::right(Val::Px(20.0)),
+                        ..default()
+                    }),
+                    LivesText,
+                ));
+                parent.spawn((
+                    TextBundle::from_section("", text_style.clone()).with_style(Style {
+                        margin: UiRect::right(Val::Px(20.0)),
+                        ..default()
+                    }),
+                    GemsText,
+                ));
+                parent.spawn((
+                    TextBundle::from_section("", text_style.clone()).with_style(Style {
+                        margin: UiRect::right(Val::Px(20.0)),
+                        ..default()
+                    }),
+                    PowerUpText,
+                ));
+                parent.spawn((
+                    TextBundle::from_section("", text_style.clone()),
+                    EndlessText,
+                ));
+            });
+
+            parent.spawn(NodeBundle::default()).with_children(|parent| {
+                for (action, label) in [
+                    (GameAction::Restart, "Restart (R)"),
+                    (GameAction::NewMaze, "New maze (N)"),
+                    (GameAction::Hint, "Hint (H)"),
+                    (GameAction::Quit, "Menu (Esc)"),
+                ] {
+                    // A seeded maze can't be replaced, see GameAction::NewMaze
+                    if action == GameAction::NewMaze && maze_config.is_seeded() {
+                        continue;
+                    }
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: NORMAL_BUTTON.into(),
+                                ..default()
+                            },
+                            action,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+                        });
+                }
+            });
+        });
+}
+
+/// Count the time in physics steps, so it is the same when a run is replayed.
+pub(crate) fn tick_timer(mut timer: ResMut<GameTimer>) {
+    timer.tick(Duration::from_secs_f32(STEP));
+}
+
+pub(crate) fn update_timer(timer: Res<GameTimer>, mut text: Query<&mut Text, With<TimerText>>) {
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = format!("{:.2}", timer.elapsed_secs());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn handle_buttons(
+    interaction_query: Query<(&Interaction, &GameAction), (Changed<Interaction>, With<Button>)>,
+    mut actions: EventWriter<GameAction>,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            actions.send(*action);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Minimap
+//!
+//! A small map of the maze in a corner of the screen, showing the marble
+//! and the goal. With [MinimapConfig::fog_of_war] only cells the marble
+//! went through are revealed.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use maze_generator::prelude::{Coordinates, Direction as MazeDirection};
+
+use crate::resources::{CurrentMaze, MinimapConfig};
+
+use super::{
+    actions::GameAction,
+    board::{Floor, Marble},
+    GameComponent,
+};
+
+const MAP_SIZE: f32 = 200.;
+const WALL_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const FOG_COLOR: Color = Color::rgba(0.1, 0.1, 0.1, 0.8);
+const CELL_COLOR: Color = Color::rgba(0.4, 0.4, 0.4, 0.8);
+const MARBLE_COLOR: Color = Color::rgb(0., 0., 1.);
+const GOAL_COLOR: Color = Color::rgb(0., 1., 0.);
+
+/// Cells the marble went through since the beginning of the run.
+#[derive(Resource, Default)]
+pub(crate) struct VisitedCells(pub(crate) HashSet<Coordinates>);
+
+#[derive(Component)]
+pub(crate) struct MinimapCell(Coordinates);
+
+pub(crate) fn setup_minimap(
+    mut commands: Commands,
+    config: Res<MinimapConfig>,
+    current_maze: Res<CurrentMaze>,
+) {
+    commands.insert_resource(VisitedCells::default());
+    if !config.visible {
+        return;
+    }
+
+    let maze = current_maze.maze();
+    let (width, height) = current_maze.size();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.),
+                    bottom: Val::Px(10.),
+                    width: Val::Px(MAP_SIZE),
+                    height: Val::Px(MAP_SIZE),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            GameComponent,
+        ))
+        .with_children(|parent| {
+            for line in 0..height {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.),
+                            height: Val::Percent(100. / height as f32),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for column in 0..width {
+                            let coordinates = Coordinates::new(column, line);
+                            let Some(field) = maze.get_field(&coordinates) else {
+                                continue;
+                            };
+                            // Same as the board : north and west walls, plus south and
+                            // east walls on the edges.
+                            let wall = |direction: MazeDirection| {
+                                if field.has_passage(&direction) {
+                                    Val::Px(0.)
+                                } else {
+                                    Val::Px(1.)
+                                }
+                            };
+                            let border = UiRect {
+                                left: wall(MazeDirection::West),
+                                right: if column == width - 1 {
+                                    Val::Px(1.)
+                                } else {
+                                    Val::Px(0.)
+                                },
+                                top: wall(MazeDirection::North),
+                                bottom: if line == height - 1 {
+                                    Val::Px(1.)
+                                } else {
+                                    Val::Px(0.)
+                                },
+                            };
+                            parent.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        width: Val::Percent(100. / width as f32),
+                                        height: Val::Percent(100.),
+                                        border,
+                                        ..default()
+                                    },
+                                    background_color: FOG_COLOR.into(),
+                                    border_color: Color::NONE.into(),
+                                    ..default()
+                                },
+                                MinimapCell(coordinates),
+                            ));
+                        }
+                    });
+            }
+        });
+}
+
+pub(crate) fn track_visited_cells(
+    current_maze: Res<CurrentMaze>,
+    mut visited: ResMut<VisitedCells>,
+    mut actions: EventReader<GameAction>,
+    floor: Query<&GlobalTransform, With<Floor>>,
+    marble: Query<&GlobalTransform, With<Marble>>,
+) {
+    if actions.iter().any(|action| *action == GameAction::Restart) {
+        visited.0.clear();
+    }
+
+    let (Ok(floor), Ok(marble)) = (floor.get_single(), marble.get_single()) else {
+        return;
+    };
+    if let Some(cell) = current_maze.cell_at_world(floor, marble.translation()) {
+        // Avoid triggering change detection when nothing changes
+        if !visited.0.contains(&cell) {
+            visited.0.insert(cell);
+        }
+    }
+}
+
+pub(crate) fn toggle_fog_of_war(keyboard: Res<Input<KeyCode>>, mut config: ResMut<MinimapConfig>) {
+    if keyboard.just_pressed(KeyCode::F) {
+        config.fog_of_war = !config.fog_of_war;
+    }
+}
+
+pub(crate) fn update_minimap(
+    config: Res<MinimapConfig>,
+    current_maze: Res<CurrentMaze>,
+    visited: Res<VisitedCells>,
+    floor: Query<&GlobalTransform, With<Floor>>,
+    marble: Query<&GlobalTransform, With<Marble>>,
+    mut cells: Query<(&MinimapCell, &mut BackgroundColor, &mut BorderColor)>,
+) {
+    let marble_cell = match (floor.get_single(), marble.get_single()) {
+        (Ok(floor), Ok(marble)) => current_maze.cell_at_world(floor, marble.translation()),
+        _ => None,
+    };
+
+    for (MinimapCell(coordinates), mut background, mut border) in &mut cells {
+        let revealed = !config.fog_of_war || visited.0.contains(coordinates);
+        let color = if marble_cell == Some(*coordinates) {
+            MARBLE_COLOR
+        } else if *coordinates == current_maze.goal() {
+            GOAL_COLOR
+        } else if revealed {
+            CELL_COLOR
+        } else {
+            FOG_COLOR
+        };
+        let wall_color = if revealed { WALL_COLOR } else { Color::NONE };
+
+        if background.0 != color {
+            background.0 = color;
+        }
+        if border.0 != wall_color {
+            border.0 = wall_color;
+        }
+    }
+}
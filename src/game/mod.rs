@@ -1,8 +1,37 @@
 use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
 
 use crate::AppState;
 
+mod actions;
+mod attract;
+mod autopilot;
 mod board;
+mod bumpers;
+mod camera;
+mod checkpoints;
+mod coop;
+mod doors;
+mod endless;
+mod gems;
+mod ghost;
+mod hint;
+mod holes;
+mod hud;
+mod minimap;
+mod obstacles;
+mod powerups;
+mod replay;
+mod replay_viewer;
+mod teleporters;
+mod tiles;
+mod versus;
+mod watchdog;
+
+pub(crate) use board::GoalReached;
+pub(crate) use coop::{start as start_coop, CoopRecords};
+pub(crate) use replay::Replay;
+pub(crate) use versus::start as start_versus;
 
 /// A simple component to mark all
 /// thing that is spawn in the game phase
@@ -13,25 +42,305 @@ struct GameComponent;
 
 pub(crate) struct GamePlugin;
 
+/// Physics on a fixed step, see the game replays : rapier runs in
+/// [FixedUpdate], after the systems setting the input of the step and before
+/// the ones reading its collisions.
+pub(crate) struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(
+            RapierPhysicsPlugin::<NoUserData>::default().with_default_system_setup(false),
+        )
+        .insert_resource(FixedTime::new_from_secs(board::STEP))
+        .insert_resource(RapierConfiguration {
+            timestep_mode: TimestepMode::Fixed {
+                dt: board::STEP,
+                substeps: 1,
+            },
+            ..default()
+        })
+        .configure_sets(
+            FixedUpdate,
+            (
+                PhysicsSet::SyncBackend,
+                PhysicsSet::StepSimulation,
+                PhysicsSet::Writeback,
+            )
+                .chain(),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::SyncBackend)
+                    .in_set(PhysicsSet::SyncBackend),
+                RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::StepSimulation)
+                    .in_set(PhysicsSet::StepSimulation),
+                RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::Writeback)
+                    .in_set(PhysicsSet::Writeback),
+            ),
+        );
+    }
+}
+
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::Game), board::setup_board)
+        app.add_event::<actions::GameAction>()
+            .add_event::<board::MarbleLost>()
+            .add_event::<board::GoalReached>()
+            .add_event::<hint::UseHint>()
+            .init_resource::<hud::GameTimer>()
+            .init_resource::<hud::RunStats>()
+            .init_resource::<hint::HintsLeft>()
+            .init_resource::<minimap::VisitedCells>()
+            .init_resource::<holes::Lives>()
+            .init_resource::<gems::Gems>()
+            .init_resource::<tiles::TilesUnderMarble>()
+            .init_resource::<teleporters::TeleportCooldown>()
+            .init_resource::<powerups::ActivePowerUps>()
+            .init_resource::<powerups::AppliedEffects>()
+            .init_resource::<board::TiltRate>()
+            .init_resource::<board::TiltInput>()
+            .init_resource::<board::Step>()
+            .init_resource::<watchdog::LastCell>()
+            .init_resource::<autopilot::Autopilot>()
+            .init_resource::<attract::AttractMaze>()
+            .init_resource::<endless::EndlessRun>()
+            .insert_resource(coop::CoopRecords::load())
+            .add_systems(
+                OnEnter(AppState::Game),
+                (
+                    endless::configure_stage,
+                    board::generate_maze,
+                    apply_deferred,
+                    (
+                        board::setup_board.run_if(not(versus::is_versus)),
+                        camera::setup_camera.run_if(not(versus::is_versus)),
+                        minimap::setup_minimap.run_if(not(versus::is_versus)),
+                        versus::setup_boards.run_if(versus::is_versus),
+                        versus::setup_cameras.run_if(versus::is_versus),
+                        coop::setup_coop.run_if(coop::is_coop),
+                        hud::setup_hud,
+                        hint::setup_hints,
+                        watchdog::setup_watchdog,
+                        replay::start_recording,
+                        replay_viewer::setup_viewer,
+                    ),
+                    // Elements on the board need the floor to be spawned
+                    apply_deferred,
+                    // Holes first, as other elements must not be behind them
+                    (
+                        holes::setup_holes,
+                        checkpoints::setup_checkpoints,
+                        gems::setup_gems,
+                        doors::setup_doors,
+                        teleporters::setup_teleporters,
+                        obstacles::setup_obstacles,
+                        bumpers::setup_bumpers,
+                        powerups::setup_power_ups,
+                        tiles::setup_tiles,
+                        ghost::setup_ghost,
+                    )
+                        .chain(),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    board::handle_keyboard_input,
+                    board::handle_gamepad_input,
+                    coop::handle_input.run_if(coop::is_coop),
+                    autopilot::toggle_autopilot,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Game)),
+            )
             .add_systems(
                 Update,
-                board::handle_keyboard_input.run_if(in_state(AppState::Game)),
+                (
+                    versus::handle_input,
+                    versus::update_viewports,
+                    versus::show_winner,
+                )
+                    .run_if(in_state(AppState::Game))
+                    .run_if(versus::is_versus),
+            )
+            // Everything the physics depends on runs on its fixed step, so a
+            // run only depends on the input of each step, see replay.
+            .add_systems(
+                FixedUpdate,
+                (
+                    autopilot::drive,
+                    replay::play_input,
+                    replay::play_hints,
+                    replay::record_input,
+                    ghost::record_trajectory,
+                    ghost::move_ghost,
+                    board::apply_tilt,
+                    board::spin,
+                    obstacles::move_blocks,
+                    bumpers::apply_wells,
+                    tiles::apply_tiles,
+                    powerups::expire_power_ups,
+                    powerups::apply_power_ups,
+                    powerups::pull_marble,
+                    hud::tick_timer,
+                )
+                    .chain()
+                    .before(PhysicsSet::SyncBackend)
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    tiles::detect_tiles,
+                    bumpers::kick_marble,
+                    teleporters::teleport_marble,
+                    powerups::collect_power_ups,
+                    gems::collect_gems,
+                    doors::pick_keys,
+                    board::detect_end_game,
+                    versus::detect_winner,
+                    checkpoints::detect_checkpoints,
+                    holes::detect_fall,
+                    watchdog::watch_marble,
+                    board::respawn_marble,
+                )
+                    .chain()
+                    .after(PhysicsSet::Writeback)
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(
+                Update,
+                (
+                    gems::reset_gems,
+                    gems::update_gems_text,
+                    doors::reset_doors,
+                    powerups::reset_power_ups,
+                    powerups::update_power_up_text,
+                    teleporters::reset_teleporters,
+                    checkpoints::reset_checkpoints,
+                    holes::reset_lives,
+                    holes::update_lives_text,
+                    replay::save_replay,
+                    ghost::save_ghost,
+                )
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(
+                Update,
+                (
+                    actions::handle_action_input,
+                    hud::handle_buttons,
+                    actions::handle_actions,
+                    ghost::hide_ghost,
+                    replay::request_hints,
+                    hint::handle_hint_actions,
+                    replay::record_hints,
+                    hud::update_timer,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Game)),
             )
             .add_systems(
                 Update,
-                board::handle_gamepad_input.run_if(in_state(AppState::Game)),
+                (hint::remove_expired_hints, hint::update_hint_text)
+                    .run_if(in_state(AppState::Game)),
             )
             .add_systems(
                 Update,
-                board::detect_end_game.run_if(in_state(AppState::Game)),
+                (
+                    minimap::toggle_fog_of_war,
+                    minimap::track_visited_cells,
+                    minimap::update_minimap,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Game)),
             )
-            .add_systems(OnExit(AppState::Game), remove::<GameComponent>);
+            .add_systems(
+                Update,
+                (
+                    camera::switch_camera_mode,
+                    camera::handle_orbit_input,
+                    camera::update_camera,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(
+                OnExit(AppState::Game),
+                (
+                    remove::<GameComponent>,
+                    board::remove_maze,
+                    checkpoints::remove_checkpoint_materials,
+                    powerups::remove_power_ups,
+                    autopilot::reset_autopilot,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    endless::next_stage,
+                    endless::update_endless_text,
+                    coop::finish_coop,
+                )
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(
+                Update,
+                (
+                    replay_viewer::handle_controls,
+                    replay_viewer::handle_timeline,
+                    replay_viewer::seek,
+                    replay_viewer::apply_viewer,
+                    replay_viewer::update_viewer,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(
+                OnEnter(AppState::Menu),
+                (attract::setup_camera, attract::spawn_maze()),
+            )
+            .add_systems(
+                Update,
+                (attract::watch_maze, attract::orbit_camera).run_if(in_state(AppState::Menu)),
+            )
+            .add_systems(
+                Update,
+                attract::spawn_maze()
+                    .after(attract::watch_maze)
+                    .run_if(in_state(AppState::Menu))
+                    .run_if(attract::needs_new_maze),
+            )
+            .add_systems(
+                FixedUpdate,
+                (attract::steer_marble, board::apply_tilt)
+                    .chain()
+                    .before(PhysicsSet::SyncBackend)
+                    .run_if(in_state(AppState::Menu)),
+            )
+            .add_systems(Update, ghost::import_ghost.run_if(in_state(AppState::Menu)))
+            .add_systems(
+                OnEnter(AppState::Menu),
+                (
+                    endless::end_run,
+                    replay::remove_playback,
+                    replay_viewer::remove_viewer,
+                    ghost::forget_imported_ghost,
+                ),
+            )
+            .add_systems(OnEnter(AppState::Loading), start_game);
     }
 }
 
+/// [AppState::Loading] only lasts one frame, it is used
+/// to despawn the current game and spawn a new one.
+fn start_game(mut state: ResMut<NextState<AppState>>) {
+    state.set(AppState::Game);
+}
+
 fn remove<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
     for entity in &to_despawn {
         commands.entity(entity).despawn_recursive();
@@ -0,0 +1,217 @@
+//! Moving obstacles
+//!
+//! Blocks sliding back and forth along long corridors and bars sweeping
+//! junctions. They are kinematic bodies parented to the floor, so they tilt
+//! with it, and their speed depends on the difficulty (see [ObstacleConfig]).
+//!
+//! None of them can seal the way : blocks only cover one side of their
+//! corridor, leaving room for the marble on the other side, while bars only
+//! cover half of their cell.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use maze_generator::prelude::Coordinates;
+use rand::seq::SliceRandom;
+
+use crate::resources::{CurrentMaze, ObstacleConfig};
+
+use super::{
+    board::{Floor, OccupiedCells, Spinning, Step, BORDER_HEIGHT, MARBLE_RADIUS, STEP},
+    GameComponent,
+};
+
+const OBSTACLE_SALT: u64 = 0x6f627374;
+/// Part of the corridor width covered by a block.
+const BLOCK_WIDTH: f32 = 0.35;
+/// Part of the cell length covered by a block, along the corridor.
+const BLOCK_LENGTH: f32 = 0.4;
+/// Seconds for a block to go back and forth, at normal difficulty.
+const BLOCK_PERIOD: f32 = 4.;
+/// Radians per second of a bar, at normal difficulty.
+const BAR_SPEED: f32 = 1.2;
+const BAR_THICKNESS: f32 = 0.03;
+const OBSTACLE_COLOR: Color = Color::rgb(0.6, 0.1, 0.1);
+
+/// A block sliding along a corridor, between the centers of the cells
+/// around its middle cell.
+#[derive(Component)]
+pub(crate) struct SlidingBlock {
+    /// Position of the block in the middle cell.
+    position: Vec3,
+    /// Offset to the next cell.
+    amplitude: Vec3,
+    period: f32,
+    /// Offset in the period, so blocks don't all move together.
+    phase: f32,
+}
+
+/// Whether `cell` is a straight corridor, and if so, if it goes along `x`.
+fn corridor_along_x(current_maze: &CurrentMaze, cell: Coordinates) -> Option<bool> {
+    match current_maze.neighbours(cell)[..] {
+        [first, second] if first.y == second.y => Some(true),
+        [first, second] if first.x == second.x => Some(false),
+        _ => None,
+    }
+}
+
+/// Middle cells of straight corridors at least three cells long.
+fn corridor_cells(current_maze: &CurrentMaze) -> Vec<(Coordinates, bool)> {
+    current_maze
+        .cells()
+        .filter_map(|cell| {
+            let along_x = corridor_along_x(current_maze, cell)?;
+            current_maze
+                .neighbours(cell)
+                .iter()
+                .all(|neighbour| corridor_along_x(current_maze, *neighbour) == Some(along_x))
+                .then_some((cell, along_x))
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn setup_obstacles(
+    mut commands: Commands,
+    config: Res<ObstacleConfig>,
+    current_maze: Res<CurrentMaze>,
+    mut occupied: ResMut<OccupiedCells>,
+    floor: Query<Entity, With<Floor>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !config.enabled {
+        return;
+    }
+    let Ok(floor) = floor.get_single() else {
+        return;
+    };
+
+    let free = |cell: &Coordinates| {
+        *cell != current_maze.start()
+            && *cell != current_maze.goal()
+            && !occupied.holes.contains(cell)
+            && !occupied.items.contains(cell)
+    };
+    let mut rng = current_maze.rng(OBSTACLE_SALT);
+    let cell_size = current_maze.cell_size();
+    // Blocks slide in the cells around their middle cell, which must be free
+    // too, and the marble must fit beside them
+    let room = cell_size.min_element() * (1. - BLOCK_WIDTH);
+    let mut blocks: Vec<(Coordinates, bool)> = if room > MARBLE_RADIUS * 2.4 {
+        corridor_cells(&current_maze)
+            .into_iter()
+            .filter(|(cell, _)| {
+                free(cell)
+                    && current_maze
+                        .neighbours(*cell)
+                        .iter()
+                        .all(|neighbour| free(neighbour))
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    blocks.shuffle(&mut rng);
+    blocks.truncate(config.blocks);
+    let mut bars: Vec<Coordinates> = current_maze
+        .cells()
+        .filter(|cell| free(cell) && current_maze.neighbours(*cell).len() >= 3)
+        .collect();
+    bars.shuffle(&mut rng);
+    bars.truncate(config.bars);
+
+    let speed_factor = config.difficulty.speed_factor();
+    let material = materials.add(OBSTACLE_COLOR.into());
+
+    for (index, (cell, along_x)) in blocks.iter().enumerate() {
+        occupied.items.insert(*cell);
+        occupied.items.extend(current_maze.neighbours(*cell));
+        // Along the corridor, on one side, alternating sides
+        let side = if index % 2 == 0 { 0.5 } else { -0.5 };
+        let (size, amplitude, offset) = if *along_x {
+            (
+                Vec3::new(
+                    cell_size.x * BLOCK_LENGTH,
+                    BORDER_HEIGHT,
+                    cell_size.y * BLOCK_WIDTH,
+                ),
+                Vec3::X * cell_size.x,
+                Vec3::Z * side * cell_size.y * (1. - BLOCK_WIDTH),
+            )
+        } else {
+            (
+                Vec3::new(
+                    cell_size.x * BLOCK_WIDTH,
+                    BORDER_HEIGHT,
+                    cell_size.y * BLOCK_LENGTH,
+                ),
+                Vec3::Z * cell_size.y,
+                Vec3::X * side * cell_size.x * (1. - BLOCK_WIDTH),
+            )
+        };
+        let position = current_maze.cell_center(*cell) + offset + Vec3::Y * BORDER_HEIGHT / 2.;
+        let block = commands
+            .spawn(PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::Box::new(size.x, size.y, size.z))),
+                material: material.clone(),
+                transform: Transform::from_translation(position),
+                ..default()
+            })
+            .insert(RigidBody::KinematicPositionBased)
+            .insert(Collider::cuboid(size.x / 2., size.y / 2., size.z / 2.))
+            .insert(SlidingBlock {
+                position,
+                amplitude,
+                period: BLOCK_PERIOD / speed_factor,
+                phase: index as f32 / blocks.len() as f32,
+            })
+            .insert(GameComponent)
+            .id();
+        commands.entity(floor).add_child(block);
+    }
+
+    let length = cell_size.min_element() * 0.45;
+    let bar_mesh = meshes.add(Mesh::from(shape::Box::new(
+        length,
+        BORDER_HEIGHT,
+        BAR_THICKNESS,
+    )));
+    for (index, cell) in bars.iter().enumerate() {
+        occupied.items.insert(*cell);
+        // Pivot on the cell center, turning both ways
+        let direction = if index % 2 == 0 { 1. } else { -1. };
+        let bar = commands
+            .spawn(SpatialBundle::from_transform(Transform::from_translation(
+                current_maze.cell_center(*cell) + Vec3::Y * BORDER_HEIGHT / 2.,
+            )))
+            .insert(RigidBody::KinematicPositionBased)
+            .insert(Spinning(direction * BAR_SPEED * speed_factor))
+            .insert(GameComponent)
+            .with_children(|parent| {
+                parent
+                    .spawn(PbrBundle {
+                        mesh: bar_mesh.clone(),
+                        material: material.clone(),
+                        transform: Transform::from_translation(Vec3::X * length / 2.),
+                        ..default()
+                    })
+                    .insert(Collider::cuboid(
+                        length / 2.,
+                        BORDER_HEIGHT / 2.,
+                        BAR_THICKNESS / 2.,
+                    ));
+            })
+            .id();
+        commands.entity(floor).add_child(bar);
+    }
+}
+
+pub(crate) fn move_blocks(step: Res<Step>, mut blocks: Query<(&mut Transform, &SlidingBlock)>) {
+    let elapsed = step.0 as f32 * STEP;
+    for (mut transform, block) in &mut blocks {
+        let angle = (elapsed / block.period + block.phase) * TAU;
+        transform.translation = block.position + block.amplitude * angle.sin();
+    }
+}
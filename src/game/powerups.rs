@@ -0,0 +1,307 @@
+//! Power-ups
+//!
+//! Pickups giving a temporary effect (see [PowerUpConfig]) :
+//! * slow motion, slowing down the physics and the tilt,
+//! * magnet, pulling the marble along the way to the goal,
+//! * phase, letting the marble go through the maze walls, but not the
+//!   border of the board.
+
+use std::{collections::HashMap, time::Duration};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use maze_generator::prelude::Coordinates;
+use rand::seq::SliceRandom;
+
+use crate::resources::{CurrentMaze, PowerUpConfig};
+
+use super::{
+    actions::GameAction,
+    board::{Border, Floor, Marble, OccupiedCells, Spinning, TiltRate, Wall, MARBLE_RADIUS, STEP},
+    GameComponent,
+};
+
+const POWER_UP_SALT: u64 = 0x706f7765;
+const POWER_UP_SPINNING_SPEED: f32 = 3.;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub(crate) enum PowerUpKind {
+    SlowMotion,
+    Magnet,
+    Phase,
+}
+
+impl PowerUpKind {
+    const ALL: [PowerUpKind; 3] = [
+        PowerUpKind::SlowMotion,
+        PowerUpKind::Magnet,
+        PowerUpKind::Phase,
+    ];
+
+    fn color(self) -> Color {
+        match self {
+            PowerUpKind::SlowMotion => Color::rgb(0.3, 0.8, 1.),
+            PowerUpKind::Magnet => Color::rgb(0.9, 0.1, 0.3),
+            PowerUpKind::Phase => Color::rgb(0.8, 0.8, 0.8),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            PowerUpKind::SlowMotion => "Slow motion",
+            PowerUpKind::Magnet => "Magnet",
+            PowerUpKind::Phase => "Phase",
+        }
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct PowerUp(PowerUpKind);
+
+/// Effects currently active, with the time they have left.
+#[derive(Resource, Default)]
+pub(crate) struct ActivePowerUps(HashMap<PowerUpKind, Timer>);
+
+/// Effects currently applied to the physics and the walls, see
+/// [apply_power_ups].
+#[derive(Resource, Default)]
+pub(crate) struct AppliedEffects {
+    slow_motion: bool,
+    phase: bool,
+}
+
+#[derive(Component)]
+pub(crate) struct PowerUpText;
+
+fn set_time_scale(rapier: &mut RapierConfiguration, scale: f32) {
+    match &mut rapier.timestep_mode {
+        TimestepMode::Variable { time_scale, .. }
+        | TimestepMode::Interpolated { time_scale, .. } => *time_scale = scale,
+        // Shorter steps, so the simulation stays deterministic
+        TimestepMode::Fixed { dt, .. } => *dt = STEP * scale,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn setup_power_ups(
+    mut commands: Commands,
+    config: Res<PowerUpConfig>,
+    current_maze: Res<CurrentMaze>,
+    mut occupied: ResMut<OccupiedCells>,
+    floor: Query<Entity, With<Floor>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(ActivePowerUps::default());
+    commands.insert_resource(AppliedEffects::default());
+    if !config.enabled {
+        return;
+    }
+    let Ok(floor) = floor.get_single() else {
+        return;
+    };
+
+    let reachable = current_maze.reachable(current_maze.start(), &occupied.holes, &[]);
+    let mut cells: Vec<Coordinates> = reachable
+        .into_iter()
+        .filter(|cell| *cell != current_maze.start() && *cell != current_maze.goal())
+        .filter(|cell| !occupied.items.contains(cell))
+        .collect();
+    // Sets aren't ordered, sort them so a maze always gets the same power-ups
+    cells.sort_by_key(|cell| (cell.y, cell.x));
+    cells.shuffle(&mut current_maze.rng(POWER_UP_SALT));
+    cells.truncate(config.count);
+
+    let size = current_maze.cell_size().min_element() * 0.15;
+    let mesh = meshes.add(
+        Mesh::try_from(shape::Icosphere {
+            radius: size,
+            subdivisions: 1,
+        })
+        .unwrap(),
+    );
+    let kind_materials: HashMap<PowerUpKind, Handle<StandardMaterial>> = PowerUpKind::ALL
+        .iter()
+        .map(|kind| {
+            let material = materials.add(StandardMaterial {
+                base_color: kind.color(),
+                emissive: kind.color() * 0.4,
+                ..default()
+            });
+            (*kind, material)
+        })
+        .collect();
+
+    for (index, cell) in cells.into_iter().enumerate() {
+        occupied.items.insert(cell);
+        let kind = PowerUpKind::ALL[index % PowerUpKind::ALL.len()];
+        let power_up = commands
+            .spawn(PbrBundle {
+                mesh: mesh.clone(),
+                material: kind_materials[&kind].clone(),
+                transform: Transform::from_translation(
+                    current_maze.cell_center(cell) + Vec3::Y * MARBLE_RADIUS,
+                ),
+                ..default()
+            })
+            .insert(Collider::ball(size))
+            .insert(Sensor)
+            .insert(PowerUp(kind))
+            .insert(Spinning(POWER_UP_SPINNING_SPEED))
+            .insert(GameComponent)
+            .id();
+        commands.entity(floor).add_child(power_up);
+    }
+}
+
+pub(crate) fn collect_power_ups(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    config: Res<PowerUpConfig>,
+    power_ups: Query<&PowerUp, Without<ColliderDisabled>>,
+    mut active: ResMut<ActivePowerUps>,
+) {
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(first, second, _) = collision_event {
+            for entity in [first, second] {
+                let Ok(PowerUp(kind)) = power_ups.get(*entity) else {
+                    continue;
+                };
+                info!("{} picked up", kind.label());
+                // Hide instead of despawning, so they can come back on restart
+                commands
+                    .entity(*entity)
+                    .insert(Visibility::Hidden)
+                    .insert(ColliderDisabled);
+                // Picking the same power-up again restarts its timer
+                active
+                    .0
+                    .insert(*kind, Timer::from_seconds(config.duration, TimerMode::Once));
+            }
+        }
+    }
+}
+
+pub(crate) fn expire_power_ups(mut active: ResMut<ActivePowerUps>) {
+    active.0.retain(|kind, timer| {
+        let finished = timer.tick(Duration::from_secs_f32(STEP)).finished();
+        if finished {
+            info!("{} expired", kind.label());
+        }
+        !finished
+    });
+}
+
+pub(crate) fn reset_power_ups(
+    mut commands: Commands,
+    mut actions: EventReader<GameAction>,
+    power_ups: Query<Entity, With<PowerUp>>,
+    mut active: ResMut<ActivePowerUps>,
+) {
+    if !actions.iter().any(|action| *action == GameAction::Restart) {
+        return;
+    }
+    active.0.clear();
+    for entity in &power_ups {
+        commands
+            .entity(entity)
+            .insert(Visibility::Inherited)
+            .remove::<ColliderDisabled>();
+    }
+}
+
+/// Start and stop the slow motion and phase effects.
+#[allow(clippy::type_complexity)]
+pub(crate) fn apply_power_ups(
+    mut commands: Commands,
+    config: Res<PowerUpConfig>,
+    active: Res<ActivePowerUps>,
+    mut rapier: ResMut<RapierConfiguration>,
+    mut tilt_rate: ResMut<TiltRate>,
+    walls: Query<Entity, (With<Wall>, Without<Border>)>,
+    mut applied: ResMut<AppliedEffects>,
+) {
+    let slow_motion = active.0.contains_key(&PowerUpKind::SlowMotion);
+    let phase = active.0.contains_key(&PowerUpKind::Phase);
+
+    if slow_motion != applied.slow_motion {
+        let scale = if slow_motion {
+            config.slow_motion_scale
+        } else {
+            1.
+        };
+        set_time_scale(&mut rapier, scale);
+        tilt_rate.0 = scale;
+    }
+
+    if phase != applied.phase {
+        for wall in &walls {
+            if phase {
+                commands.entity(wall).insert(ColliderDisabled);
+            } else {
+                commands.entity(wall).remove::<ColliderDisabled>();
+            }
+        }
+    }
+
+    applied.slow_motion = slow_motion;
+    applied.phase = phase;
+}
+
+/// Pull the marble toward the next cell on the way to the goal.
+pub(crate) fn pull_marble(
+    config: Res<PowerUpConfig>,
+    active: Res<ActivePowerUps>,
+    current_maze: Res<CurrentMaze>,
+    floor: Query<&GlobalTransform, With<Floor>>,
+    mut marble: Query<(&GlobalTransform, &mut Velocity), With<Marble>>,
+) {
+    if !active.0.contains_key(&PowerUpKind::Magnet) {
+        return;
+    }
+    let (Ok(floor), Ok((transform, mut velocity))) = (floor.get_single(), marble.get_single_mut())
+    else {
+        return;
+    };
+
+    let position = floor
+        .affine()
+        .inverse()
+        .transform_point3(transform.translation());
+    let Some(cell) = current_maze.cell_at(position) else {
+        return;
+    };
+    let path = current_maze.path(cell, current_maze.goal());
+    let target = current_maze.cell_center(*path.get(1).unwrap_or(&current_maze.goal()));
+    let direction = Vec3::new(target.x - position.x, 0., target.z - position.z).normalize_or_zero();
+    velocity.linvel += floor.affine().transform_vector3(direction) * config.magnet_strength * STEP;
+}
+
+/// Restore the normal physics when leaving the game.
+pub(crate) fn remove_power_ups(
+    mut rapier: ResMut<RapierConfiguration>,
+    mut tilt_rate: ResMut<TiltRate>,
+    mut active: ResMut<ActivePowerUps>,
+) {
+    set_time_scale(&mut rapier, 1.);
+    tilt_rate.0 = 1.;
+    active.0.clear();
+}
+
+pub(crate) fn update_power_up_text(
+    active: Res<ActivePowerUps>,
+    mut text: Query<&mut Text, With<PowerUpText>>,
+) {
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = PowerUpKind::ALL
+            .iter()
+            .filter_map(|kind| {
+                active
+                    .0
+                    .get(kind)
+                    .map(|timer| format!("{} : {:.1}s", kind.label(), timer.remaining_secs()))
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+    }
+}
@@ -0,0 +1,231 @@
+//! Replays
+//!
+//! Runs are recorded as the [TiltInput] of each physics step and the steps
+//! hints were used at, along with the maze and the mechanics they were played
+//! with. The physics running on a
+//! fixed step, feeding the same inputs back gives the same run on the same
+//! build. The last run that reached the goal is saved and can be watched from
+//! the menu, see [super::replay_viewer].
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    options::{GameOption, GameOptions},
+    resources::{CurrentMaze, GameMode, MazeConfig},
+    storage,
+};
+
+use super::{
+    actions::GameAction,
+    board::{GoalReached, Step, TiltInput},
+    hint::UseHint,
+    replay_viewer::ReplayViewer,
+};
+
+const SAVE_KEY: &str = "replay";
+
+/// A recorded run.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Replay {
+    maze: MazeConfig,
+    mechanics: Vec<GameOption>,
+    /// Input of the physics steps, with the number of steps it lasted.
+    inputs: Vec<(u32, TiltInput)>,
+    /// Steps at which a hint was used, for its time penalty.
+    #[serde(default)]
+    hints: Vec<u32>,
+}
+
+impl Replay {
+    /// The last saved replay, if any.
+    pub(crate) fn load_saved() -> Option<Self> {
+        storage::load(SAVE_KEY)
+    }
+
+    /// Configure the game to play the replay back.
+    pub(crate) fn watch(
+        &self,
+        commands: &mut Commands,
+        mode: &mut GameMode,
+        maze_config: &mut MazeConfig,
+        options: &mut GameOptions,
+    ) {
+        *maze_config = self.maze;
+        options.keep_user_options();
+        for option in GameOption::ALL {
+            options.set(option, self.mechanics.contains(&option));
+        }
+        *mode = GameMode::Replay;
+        commands.insert_resource(ReplayViewer::default());
+        commands.insert_resource(Playback {
+            inputs: self
+                .inputs
+                .iter()
+                .flat_map(|(steps, input)| std::iter::repeat(*input).take(*steps as usize))
+                .collect(),
+            hints: self.hints.clone(),
+        });
+    }
+
+    fn push(&mut self, input: TiltInput) {
+        match self.inputs.last_mut() {
+            Some((steps, last)) if *last == input => *steps += 1,
+            _ => self.inputs.push((1, input)),
+        }
+    }
+}
+
+/// The run being played.
+#[derive(Resource)]
+pub(crate) struct Recording(Replay);
+
+/// The replay being watched.
+#[derive(Resource)]
+pub(crate) struct Playback {
+    /// Input of each step.
+    inputs: Vec<TiltInput>,
+    hints: Vec<u32>,
+}
+
+impl Playback {
+    /// Number of recorded steps.
+    pub(crate) fn steps(&self) -> usize {
+        self.inputs.len()
+    }
+}
+
+pub(crate) fn start_recording(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    current_maze: Res<CurrentMaze>,
+    options: GameOptions,
+) {
+    // A replay has a single board
+    if matches!(*mode, GameMode::Replay | GameMode::Versus) {
+        commands.remove_resource::<Recording>();
+        return;
+    }
+    commands.insert_resource(Recording(Replay {
+        maze: *current_maze.config(),
+        mechanics: options.enabled_options(),
+        inputs: Vec::new(),
+        hints: Vec::new(),
+    }));
+}
+
+pub(crate) fn record_input(
+    step: Res<Step>,
+    tilt: Res<TiltInput>,
+    recording: Option<ResMut<Recording>>,
+) {
+    let Some(mut recording) = recording else {
+        return;
+    };
+    // The run has been restarted
+    if step.0 == 0 {
+        recording.0.inputs.clear();
+        recording.0.hints.clear();
+    }
+    recording.0.push(*tilt);
+}
+
+pub(crate) fn record_hints(
+    step: Res<Step>,
+    mut hints: EventReader<UseHint>,
+    recording: Option<ResMut<Recording>>,
+) {
+    let Some(mut recording) = recording else {
+        hints.clear();
+        return;
+    };
+    for _ in hints.iter() {
+        recording.0.hints.push(step.0);
+    }
+}
+
+/// Hints are the ones of the player, unless a replay is watched.
+pub(crate) fn request_hints(
+    mut actions: EventReader<GameAction>,
+    playback: Option<Res<Playback>>,
+    mut hints: EventWriter<UseHint>,
+) {
+    for action in actions.iter() {
+        if *action == GameAction::Hint && playback.is_none() {
+            hints.send(UseHint);
+        }
+    }
+}
+
+/// Replace the player input by the recorded one.
+pub(crate) fn play_input(
+    step: Res<Step>,
+    playback: Option<Res<Playback>>,
+    mut tilt: ResMut<TiltInput>,
+) {
+    if let Some(playback) = playback {
+        // Once the recording is over, the floor stays still
+        *tilt = playback
+            .inputs
+            .get(step.0 as usize)
+            .copied()
+            .unwrap_or_default();
+    }
+}
+
+/// Use the recorded hints at the step they were used.
+pub(crate) fn play_hints(
+    step: Res<Step>,
+    playback: Option<Res<Playback>>,
+    mut hints: EventWriter<UseHint>,
+) {
+    if let Some(playback) = playback {
+        for _ in playback.hints.iter().filter(|hint| **hint == step.0) {
+            hints.send(UseHint);
+        }
+    }
+}
+
+/// Save the run once the goal is reached, or stop at the end of a replay.
+pub(crate) fn save_replay(
+    mut reached: EventReader<GoalReached>,
+    recording: Option<Res<Recording>>,
+    viewer: Option<ResMut<ReplayViewer>>,
+) {
+    if reached.is_empty() {
+        return;
+    }
+    reached.clear();
+    if let Some(mut viewer) = viewer {
+        info!("End of the replay");
+        viewer.pause();
+    } else if let Some(recording) = recording {
+        storage::save(SAVE_KEY, &recording.0);
+    }
+}
+
+/// Stop watching the replay.
+pub(crate) fn remove_playback(mut commands: Commands) {
+    commands.remove_resource::<Playback>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_merges_repeated_inputs() {
+        let still = TiltInput::default();
+        let left = TiltInput { x: -1., z: 0. };
+        let mut replay = Replay {
+            maze: MazeConfig::default(),
+            mechanics: Vec::new(),
+            inputs: Vec::new(),
+            hints: Vec::new(),
+        };
+        for input in [still, still, still, left, still, still] {
+            replay.push(input);
+        }
+        assert!(replay.inputs == vec![(3, still), (1, left), (2, still)]);
+    }
+}
@@ -0,0 +1,380 @@
+//! Replay viewer
+//!
+//! Controls shown while watching a replay : pause (Space), step by step when
+//! paused (Enter), speed (- and + or =) and a timeline to jump anywhere in the
+//! run. The camera mode can be switched as usual.
+//!
+//! Pause and speed act on the time, so the physics steps follow. Jumping
+//! back restarts the maze and plays the steps again as fast as possible.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::AppState;
+
+use super::{
+    board::{Step, STEP},
+    replay::Playback,
+    GameComponent,
+};
+
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const NORMAL_BUTTON: Color = Color::rgb(0.3, 0.3, 0.3);
+const TIMELINE_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+const PROGRESS_COLOR: Color = Color::rgb(0.2, 0.5, 0.9);
+const SPEEDS: [f32; 6] = [0.1, 0.25, 0.5, 1., 2., 4.];
+const NORMAL_SPEED: usize = 3;
+
+/// State of the replay viewer, kept while the replay is watched.
+#[derive(Resource)]
+pub(crate) struct ReplayViewer {
+    paused: bool,
+    /// Index in [SPEEDS].
+    speed: usize,
+    /// Step to jump to.
+    seek: Option<u32>,
+}
+
+impl Default for ReplayViewer {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            speed: NORMAL_SPEED,
+            seek: None,
+        }
+    }
+}
+
+impl ReplayViewer {
+    /// Stop at the end of the replay.
+    pub(crate) fn pause(&mut self) {
+        self.paused = true;
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+pub(crate) enum ReplayControl {
+    Pause,
+    Step,
+    Slower,
+    Faster,
+}
+
+#[derive(Component)]
+pub(crate) struct Timeline;
+
+#[derive(Component)]
+pub(crate) struct TimelineProgress;
+
+#[derive(Component)]
+pub(crate) struct ReplayText;
+
+pub(crate) fn setup_viewer(mut commands: Commands, viewer: Option<Res<ReplayViewer>>) {
+    if viewer.is_none() {
+        return;
+    }
+
+    let button_style = Style {
+        width: Val::Px(110.0),
+        height: Val::Px(40.0),
+        margin: UiRect::all(Val::Px(5.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let text_style = TextStyle {
+        font_size: 25.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            GameComponent,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Percent(80.0),
+                            height: Val::Px(16.0),
+                            margin: UiRect::all(Val::Px(5.0)),
+                            ..default()
+                        },
+                        background_color: TIMELINE_COLOR.into(),
+                        ..default()
+                    },
+                    Timeline,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: PROGRESS_COLOR.into(),
+                            ..default()
+                        },
+                        TimelineProgress,
+                    ));
+                });
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (control, label) in [
+                        (ReplayControl::Slower, "Slower (-)"),
+                        (ReplayControl::Pause, "Pause (Space)"),
+                        (ReplayControl::Step, "Step (Enter)"),
+                        (ReplayControl::Faster, "Faster (+)"),
+                    ] {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON.into(),
+                                    ..default()
+                                },
+                                control,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    label,
+                                    TextStyle {
+                                        font_size: 18.0,
+                                        ..text_style.clone()
+                                    },
+                                ));
+                            });
+                    }
+                    parent.spawn((
+                        TextBundle::from_section("", text_style.clone()).with_style(Style {
+                            margin: UiRect::left(Val::Px(20.0)),
+                            ..default()
+                        }),
+                        ReplayText,
+                    ));
+                });
+        });
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn handle_controls(
+    keyboard: Res<Input<KeyCode>>,
+    interaction_query: Query<(&Interaction, &ReplayControl), (Changed<Interaction>, With<Button>)>,
+    viewer: Option<ResMut<ReplayViewer>>,
+    mut fixed_time: ResMut<FixedTime>,
+) {
+    let Some(mut viewer) = viewer else {
+        return;
+    };
+
+    let mut controls: Vec<ReplayControl> = interaction_query
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, control)| *control)
+        .collect();
+    for (key, control) in [
+        (KeyCode::Space, ReplayControl::Pause),
+        (KeyCode::Return, ReplayControl::Step),
+        (KeyCode::Minus, ReplayControl::Slower),
+        (KeyCode::NumpadSubtract, ReplayControl::Slower),
+        // Plus is on the same key as equals on most layouts
+        (KeyCode::Equals, ReplayControl::Faster),
+        (KeyCode::NumpadAdd, ReplayControl::Faster),
+    ] {
+        if keyboard.just_pressed(key) {
+            controls.push(control);
+        }
+    }
+
+    for control in controls {
+        match control {
+            ReplayControl::Pause => viewer.paused = !viewer.paused,
+            // Let exactly one more physics step run
+            ReplayControl::Step if viewer.paused => {
+                fixed_time.tick(Duration::from_secs_f32(STEP));
+            }
+            ReplayControl::Step => (),
+            ReplayControl::Slower => viewer.speed = viewer.speed.saturating_sub(1),
+            ReplayControl::Faster => viewer.speed = (viewer.speed + 1).min(SPEEDS.len() - 1),
+        }
+    }
+}
+
+/// Jump to the step under the cursor when the timeline is clicked.
+#[allow(clippy::type_complexity)]
+pub(crate) fn handle_timeline(
+    windows: Query<&Window>,
+    timeline: Query<
+        (&Interaction, &Node, &GlobalTransform),
+        (Changed<Interaction>, With<Timeline>),
+    >,
+    playback: Option<Res<Playback>>,
+    viewer: Option<ResMut<ReplayViewer>>,
+) {
+    let (Some(playback), Some(mut viewer), Ok((interaction, node, transform))) =
+        (playback, viewer, timeline.get_single())
+    else {
+        return;
+    };
+    if *interaction != Interaction::Pressed {
+        return;
+    }
+    let Some(cursor) = windows.get_single().ok().and_then(Window::cursor_position) else {
+        return;
+    };
+
+    let left = transform.translation().x - node.size().x / 2.;
+    let ratio = ((cursor.x - left) / node.size().x).clamp(0., 1.);
+    viewer.seek = Some((ratio * playback.steps() as f32) as u32);
+}
+
+/// Run the physics steps up to the one to jump to.
+pub(crate) fn seek(
+    step: Res<Step>,
+    viewer: Option<ResMut<ReplayViewer>>,
+    mut fixed_time: ResMut<FixedTime>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    let Some(mut viewer) = viewer else {
+        return;
+    };
+    let Some(target) = viewer.seek else {
+        return;
+    };
+
+    if target < step.0 {
+        // Steps can't be undone, start the maze again, seeking goes on
+        // once it is loaded
+        state.set(AppState::Loading);
+        return;
+    }
+    fixed_time.tick(Duration::from_secs_f32(STEP) * (target - step.0));
+    viewer.seek = None;
+}
+
+/// Pause and set the speed of the time, the fixed steps being run according
+/// to it.
+pub(crate) fn apply_viewer(viewer: Option<Res<ReplayViewer>>, mut time: ResMut<Time>) {
+    let Some(viewer) = viewer else {
+        return;
+    };
+    if !viewer.is_changed() {
+        return;
+    }
+    if viewer.paused {
+        time.pause();
+    } else {
+        time.unpause();
+    }
+    time.set_relative_speed(SPEEDS[viewer.speed]);
+}
+
+pub(crate) fn update_viewer(
+    step: Res<Step>,
+    playback: Option<Res<Playback>>,
+    viewer: Option<Res<ReplayViewer>>,
+    mut progress: Query<&mut Style, With<TimelineProgress>>,
+    mut text: Query<&mut Text, With<ReplayText>>,
+) {
+    let (Some(playback), Some(viewer)) = (playback, viewer) else {
+        return;
+    };
+    let steps = playback.steps().max(1);
+
+    if let Ok(mut style) = progress.get_single_mut() {
+        style.width = Val::Percent((step.0 as f32 / steps as f32).min(1.) * 100.);
+    }
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = format!(
+            "Step {}/{} - x{}{}",
+            step.0,
+            steps,
+            SPEEDS[viewer.speed],
+            if viewer.paused { " - Paused" } else { "" }
+        );
+    }
+}
+
+/// Leave the time as it was before the replay.
+pub(crate) fn remove_viewer(mut commands: Commands, mut time: ResMut<Time>) {
+    commands.remove_resource::<ReplayViewer>();
+    time.unpause();
+    time.set_relative_speed(1.);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+
+    use crate::game::board::{self, TiltInput, TiltRate};
+
+    use super::*;
+
+    fn steps(app: &App) -> u32 {
+        app.world.resource::<Step>().0
+    }
+
+    #[test]
+    fn step_runs_one_physics_step_when_paused() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                STEP,
+            )))
+            .insert_resource(FixedTime::new_from_secs(STEP))
+            .insert_resource(ReplayViewer {
+                paused: true,
+                ..default()
+            })
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<Step>()
+            .init_resource::<TiltInput>()
+            .init_resource::<TiltRate>()
+            .add_systems(Update, (handle_controls, apply_viewer).chain())
+            .add_systems(FixedUpdate, board::apply_tilt);
+
+        // The first frames may run before the time is paused
+        for _ in 0..3 {
+            app.update();
+        }
+        let paused_at = steps(&app);
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(steps(&app), paused_at);
+
+        app.world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::Return);
+        app.update();
+        app.world.resource_mut::<Input<KeyCode>>().clear();
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(steps(&app), paused_at + 1);
+    }
+}
@@ -0,0 +1,176 @@
+//! Teleporters
+//!
+//! Pairs of pads of the same color. When the marble rolls on a pad, it is
+//! sent to the other pad of the pair. Pads are then inactive for a while
+//! (see [TeleporterConfig]) so the marble can leave the pad it arrived on.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use maze_generator::prelude::Coordinates;
+use rand::seq::SliceRandom;
+
+use crate::resources::{CurrentMaze, TeleporterConfig};
+
+use super::{
+    actions::GameAction,
+    board::{Floor, Marble, OccupiedCells, Spinning, MARBLE_RADIUS, STEP},
+    GameComponent,
+};
+
+const TELEPORTER_SALT: u64 = 0x74656c65;
+const RING_SPINNING_SPEED: f32 = 1.5;
+const COLORS: [Color; 4] = [
+    Color::rgb(0.6, 0.2, 1.),
+    Color::rgb(0.1, 0.9, 0.5),
+    Color::rgb(1., 0.3, 0.6),
+    Color::rgb(0.2, 0.6, 1.),
+];
+
+/// A pad sending the marble to its partner.
+#[derive(Component)]
+pub(crate) struct Teleporter {
+    /// Where the marble arrives, in the floor local space.
+    destination: Vec3,
+}
+
+/// Time left before pads can be used again, `None` when they can.
+#[derive(Resource, Default)]
+pub(crate) struct TeleportCooldown(Option<Timer>);
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn setup_teleporters(
+    mut commands: Commands,
+    config: Res<TeleporterConfig>,
+    current_maze: Res<CurrentMaze>,
+    mut occupied: ResMut<OccupiedCells>,
+    floor: Query<Entity, With<Floor>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(TeleportCooldown::default());
+    if !config.enabled {
+        return;
+    }
+    let Ok(floor) = floor.get_single() else {
+        return;
+    };
+
+    // Only where the marble can go, so it can't be sent behind a hole
+    let reachable = current_maze.reachable(current_maze.start(), &occupied.holes, &[]);
+    let mut cells: Vec<Coordinates> = reachable
+        .into_iter()
+        .filter(|cell| *cell != current_maze.start() && *cell != current_maze.goal())
+        .filter(|cell| !occupied.items.contains(cell))
+        .collect();
+    // Sets aren't ordered, sort them so a maze always gets the same pads
+    cells.sort_by_key(|cell| (cell.y, cell.x));
+    cells.shuffle(&mut current_maze.rng(TELEPORTER_SALT));
+
+    let radius = current_maze.cell_size().min_element() * 0.35;
+    let pad_mesh = meshes.add(Mesh::from(shape::Cylinder {
+        radius,
+        height: 0.004,
+        ..default()
+    }));
+    let ring_mesh = meshes.add(Mesh::from(shape::Torus {
+        radius: radius * 0.8,
+        ring_radius: radius * 0.08,
+        ..default()
+    }));
+
+    for (number, pair) in cells
+        .chunks_exact(2)
+        .take(config.pairs.min(COLORS.len()))
+        .enumerate()
+    {
+        let color = COLORS[number];
+        let material = materials.add(StandardMaterial {
+            base_color: color,
+            emissive: color * 0.5,
+            ..default()
+        });
+        for (cell, partner) in [(pair[0], pair[1]), (pair[1], pair[0])] {
+            occupied.items.insert(cell);
+            let pad = commands
+                .spawn(PbrBundle {
+                    mesh: pad_mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(
+                        current_maze.cell_center(cell) + Vec3::Y * 0.002,
+                    ),
+                    ..default()
+                })
+                .insert(Collider::cylinder(0.005, radius * 0.5))
+                .insert(Sensor)
+                .insert(Teleporter {
+                    destination: current_maze.cell_center(partner)
+                        + Vec3::Y * (MARBLE_RADIUS + 0.01),
+                })
+                .insert(GameComponent)
+                .with_children(|parent| {
+                    // Floating ring of the pair color
+                    parent
+                        .spawn(PbrBundle {
+                            mesh: ring_mesh.clone(),
+                            material: material.clone(),
+                            transform: Transform::from_translation(Vec3::Y * MARBLE_RADIUS * 2.)
+                                .with_rotation(Quat::from_rotation_x(0.3)),
+                            ..default()
+                        })
+                        .insert(Spinning(RING_SPINNING_SPEED));
+                })
+                .id();
+            commands.entity(floor).add_child(pad);
+        }
+    }
+}
+
+pub(crate) fn teleport_marble(
+    config: Res<TeleporterConfig>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut cooldown: ResMut<TeleportCooldown>,
+    teleporters: Query<&Teleporter>,
+    floor: Query<&GlobalTransform, With<Floor>>,
+    mut marble: Query<(&mut Transform, &mut Velocity), With<Marble>>,
+) {
+    if let Some(timer) = &mut cooldown.0 {
+        if timer.tick(Duration::from_secs_f32(STEP)).finished() {
+            cooldown.0 = None;
+        }
+    }
+
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(first, second, _) = collision_event {
+            for entity in [first, second] {
+                let Ok(teleporter) = teleporters.get(*entity) else {
+                    continue;
+                };
+                if cooldown.0.is_some() {
+                    continue;
+                }
+                let (Ok(floor), Ok((mut transform, mut velocity))) =
+                    (floor.get_single(), marble.get_single_mut())
+                else {
+                    continue;
+                };
+                info!("Marble teleported");
+                transform.translation = floor.transform_point(teleporter.destination);
+                if !config.keep_velocity {
+                    *velocity = Velocity::zero();
+                }
+                cooldown.0 = Some(Timer::from_seconds(config.cooldown, TimerMode::Once));
+            }
+        }
+    }
+}
+
+pub(crate) fn reset_teleporters(
+    mut actions: EventReader<GameAction>,
+    mut cooldown: ResMut<TeleportCooldown>,
+) {
+    if actions.iter().any(|action| *action == GameAction::Restart) {
+        cooldown.0 = None;
+    }
+}
@@ -0,0 +1,249 @@
+//! Special floor tiles
+//!
+//! Some cells get a tile changing how the marble moves : ice where it
+//! slides, mud slowing it down, boosters kicking it in one direction and
+//! conveyor belts pushing it as long as it stays on them.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use maze_generator::prelude::Coordinates;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::resources::{CurrentMaze, TileConfig};
+
+use super::{
+    board::{Floor, Marble, OccupiedCells, STEP},
+    GameComponent,
+};
+
+const TILE_SALT: u64 = 0x74696c65;
+const ICE_FRICTION: f32 = 0.;
+const MUD_DAMPING: f32 = 4.;
+/// Speed given by a booster, in the direction of its arrow.
+const BOOST_SPEED: f32 = 1.5;
+/// Acceleration of a conveyor belt.
+const CONVEYOR_ACCELERATION: f32 = 1.;
+const ICE_COLOR: Color = Color::rgb(0.75, 0.9, 1.);
+const MUD_COLOR: Color = Color::rgb(0.4, 0.25, 0.1);
+const BOOSTER_COLOR: Color = Color::rgb(1., 0.4, 0.);
+const CONVEYOR_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+const ARROW_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+
+#[derive(Clone, Copy)]
+pub(crate) enum TileKind {
+    Ice,
+    Mud,
+    /// Kick the marble once along the direction, in the floor local space.
+    Booster(Vec3),
+    /// Push the marble along the direction while it is on the tile.
+    Conveyor(Vec3),
+}
+
+#[derive(Component)]
+pub(crate) struct Tile(TileKind);
+
+/// Tiles the marble is currently on.
+#[derive(Resource, Default)]
+pub(crate) struct TilesUnderMarble(HashSet<Entity>);
+
+/// Pick the kind of the tile of `cell`, directional tiles pointing to one of
+/// the neighbours.
+fn tile_kind(current_maze: &CurrentMaze, cell: Coordinates, rng: &mut impl Rng) -> TileKind {
+    let direction = current_maze
+        .neighbours(cell)
+        .choose(rng)
+        .map(|neighbour| {
+            (current_maze.cell_center(*neighbour) - current_maze.cell_center(cell)).normalize()
+        })
+        .unwrap_or(Vec3::X);
+    match rng.gen_range(0, 4) {
+        0 => TileKind::Ice,
+        1 => TileKind::Mud,
+        2 => TileKind::Booster(direction),
+        _ => TileKind::Conveyor(direction),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn setup_tiles(
+    mut commands: Commands,
+    config: Res<TileConfig>,
+    current_maze: Res<CurrentMaze>,
+    occupied: Res<OccupiedCells>,
+    floor: Query<Entity, With<Floor>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(TilesUnderMarble::default());
+    if !config.enabled {
+        return;
+    }
+    let Ok(floor) = floor.get_single() else {
+        return;
+    };
+
+    let mut cells: Vec<Coordinates> = current_maze
+        .cells()
+        .filter(|cell| *cell != current_maze.start() && *cell != current_maze.goal())
+        .filter(|cell| !occupied.holes.contains(cell) && !occupied.items.contains(cell))
+        .collect();
+    let mut rng = current_maze.rng(TILE_SALT);
+    cells.shuffle(&mut rng);
+    cells.truncate((cells.len() as f32 * config.density).round() as usize);
+
+    let size = current_maze.cell_size() * 0.9;
+    let mesh = meshes.add(Mesh::from(shape::Box::new(size.x, 0.002, size.y)));
+    let ice = materials.add(StandardMaterial {
+        base_color: ICE_COLOR,
+        perceptual_roughness: 0.1,
+        reflectance: 0.8,
+        ..default()
+    });
+    let mud = materials.add(StandardMaterial {
+        base_color: MUD_COLOR,
+        perceptual_roughness: 1.,
+        ..default()
+    });
+    let booster = materials.add(StandardMaterial {
+        base_color: BOOSTER_COLOR,
+        emissive: BOOSTER_COLOR * 0.3,
+        ..default()
+    });
+    let conveyor = materials.add(CONVEYOR_COLOR.into());
+
+    // Chevron pointing toward `z`, rotated along the tile direction
+    let arrow_length = size.min_element() * 0.3;
+    let arrow_mesh = meshes.add(Mesh::from(shape::Box::new(
+        arrow_length * 0.15,
+        0.002,
+        arrow_length,
+    )));
+    let arrow_material = materials.add(ARROW_COLOR.into());
+
+    for cell in cells {
+        let kind = tile_kind(&current_maze, cell, &mut rng);
+        let (material, direction) = match kind {
+            TileKind::Ice => (ice.clone(), None),
+            TileKind::Mud => (mud.clone(), None),
+            TileKind::Booster(direction) => (booster.clone(), Some(direction)),
+            TileKind::Conveyor(direction) => (conveyor.clone(), Some(direction)),
+        };
+        let tile = commands
+            .spawn(PbrBundle {
+                mesh: mesh.clone(),
+                material,
+                transform: Transform::from_translation(
+                    current_maze.cell_center(cell) + Vec3::Y * 0.0015,
+                ),
+                ..default()
+            })
+            .insert(Collider::cuboid(size.x / 2., 0.005, size.y / 2.))
+            .insert(Sensor)
+            .insert(Tile(kind))
+            .insert(GameComponent)
+            .with_children(|parent| {
+                let Some(direction) = direction else {
+                    return;
+                };
+                let rotation = Quat::from_rotation_arc(Vec3::Z, direction);
+                for side in [-1., 1.] {
+                    let branch = Quat::from_rotation_y(side * std::f32::consts::FRAC_PI_4);
+                    let offset = branch * Vec3::new(0., 0., -arrow_length / 2.);
+                    parent.spawn(PbrBundle {
+                        mesh: arrow_mesh.clone(),
+                        material: arrow_material.clone(),
+                        transform: Transform::from_translation(rotation * offset + Vec3::Y * 0.001)
+                            .with_rotation(rotation * branch),
+                        ..default()
+                    });
+                }
+            })
+            .id();
+        commands.entity(floor).add_child(tile);
+    }
+}
+
+pub(crate) fn detect_tiles(
+    mut collision_events: EventReader<CollisionEvent>,
+    tiles: Query<&Tile>,
+    floor: Query<&GlobalTransform, With<Floor>>,
+    mut marble: Query<&mut Velocity, With<Marble>>,
+    mut under_marble: ResMut<TilesUnderMarble>,
+) {
+    for collision_event in collision_events.iter() {
+        match collision_event {
+            CollisionEvent::Started(first, second, _) => {
+                for entity in [first, second] {
+                    let Ok(Tile(kind)) = tiles.get(*entity) else {
+                        continue;
+                    };
+                    under_marble.0.insert(*entity);
+                    if let (TileKind::Booster(direction), Ok(floor), Ok(mut velocity)) =
+                        (kind, floor.get_single(), marble.get_single_mut())
+                    {
+                        let (_, rotation, _) = floor.to_scale_rotation_translation();
+                        velocity.linvel += rotation * *direction * BOOST_SPEED;
+                    }
+                }
+            }
+            CollisionEvent::Stopped(first, second, _) => {
+                under_marble.0.remove(first);
+                under_marble.0.remove(second);
+            }
+        }
+    }
+}
+
+/// Change the marble physics according to the tiles it is on.
+pub(crate) fn apply_tiles(
+    under_marble: Res<TilesUnderMarble>,
+    tiles: Query<&Tile>,
+    floor: Query<&GlobalTransform, With<Floor>>,
+    mut marble: Query<(&mut Friction, &mut Damping, &mut Velocity), With<Marble>>,
+) {
+    let (Ok(floor), Ok((mut friction, mut damping, mut velocity))) =
+        (floor.get_single(), marble.get_single_mut())
+    else {
+        return;
+    };
+    let (_, rotation, _) = floor.to_scale_rotation_translation();
+
+    let mut on_ice = false;
+    let mut in_mud = false;
+    for Tile(kind) in tiles.iter_many(&under_marble.0) {
+        match kind {
+            TileKind::Ice => on_ice = true,
+            TileKind::Mud => in_mud = true,
+            TileKind::Booster(_) => (),
+            TileKind::Conveyor(direction) => {
+                velocity.linvel += rotation * *direction * CONVEYOR_ACCELERATION * STEP;
+            }
+        }
+    }
+
+    // Only touch the components when needed, to keep change detection meaningful
+    let wanted_friction = if on_ice {
+        Friction {
+            coefficient: ICE_FRICTION,
+            combine_rule: CoefficientCombineRule::Min,
+        }
+    } else {
+        Friction::default()
+    };
+    if *friction != wanted_friction {
+        *friction = wanted_friction;
+    }
+    let wanted_damping = if in_mud {
+        Damping {
+            linear_damping: MUD_DAMPING,
+            angular_damping: MUD_DAMPING,
+        }
+    } else {
+        Damping::default()
+    };
+    if *damping != wanted_damping {
+        *damping = wanted_damping;
+    }
+}
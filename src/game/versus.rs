@@ -0,0 +1,238 @@
+//! Versus mode
+//!
+//! Two players race side by side on the same maze, each with their own board,
+//! half of the screen and controls : Z, Q, S, D or the first gamepad for the
+//! left board, the arrows or the second gamepad for the right one. The first
+//! marble to reach its goal wins.
+//!
+//! Races are played without the optional mechanics, their state (gems,
+//! lives, keys...) being kept for a single marble.
+
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig, prelude::*, render::camera::Viewport,
+    window::PrimaryWindow,
+};
+use bevy_rapier3d::{prelude::*, rapier::prelude::CollisionEventFlags};
+
+use crate::{
+    options::{GameOption, GameOptions},
+    resources::{CurrentMaze, GameMode, MazeConfig},
+    AppState,
+};
+
+use super::{
+    board::{self, Goal, Marble, Player, Step, TiltInput},
+    hud::GameTimer,
+    GameComponent,
+};
+
+const CAMERA_OFFSET: Vec3 = Vec3::new(0., 8., 4.5);
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+/// Seconds the winner is shown before going back to the menu.
+const WINNER_DURATION: f32 = 3.;
+
+/// The player who reached the goal first.
+#[derive(Resource)]
+pub(crate) struct Winner {
+    player: Player,
+    timer: Timer,
+}
+
+#[derive(Component)]
+pub(crate) struct VersusText;
+
+/// Configure the game for a race.
+pub(crate) fn start(mode: &mut GameMode, maze_config: &mut MazeConfig, options: &mut GameOptions) {
+    *mode = GameMode::Versus;
+    *maze_config = MazeConfig::default();
+    options.keep_user_options();
+    for option in GameOption::ALL {
+        options.set(option, false);
+    }
+}
+
+pub(crate) fn is_versus(mode: Res<GameMode>) -> bool {
+    *mode == GameMode::Versus
+}
+
+/// Spawn a board for each player, instead of [board::setup_board].
+pub(crate) fn setup_boards(
+    mut commands: Commands,
+    current_maze: Res<CurrentMaze>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(Step::default());
+    commands.insert_resource(TiltInput::default());
+    commands.remove_resource::<Winner>();
+
+    commands
+        .spawn(PointLightBundle {
+            point_light: PointLight {
+                intensity: 3000.0,
+                shadows_enabled: true,
+                ..default()
+            },
+            transform: Transform::from_xyz(0., 10., 4.),
+            ..default()
+        })
+        .insert(GameComponent);
+
+    for player in Player::ALL {
+        let (floor, marble) = board::spawn_board(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &current_maze,
+            player.board_center(),
+        );
+        commands
+            .entity(floor)
+            .insert((player, TiltInput::default()));
+        commands.entity(marble).insert(player);
+    }
+}
+
+/// A camera on each half of the screen, instead of [super::camera::setup_camera],
+/// and one for the interface on top of them.
+pub(crate) fn setup_cameras(mut commands: Commands) {
+    for player in Player::ALL {
+        let center = player.board_center();
+        commands
+            .spawn(Camera3dBundle {
+                camera: Camera {
+                    order: player.index() as isize,
+                    ..default()
+                },
+                transform: Transform::from_translation(center + CAMERA_OFFSET)
+                    .looking_at(center, Vec3::Y),
+                ..default()
+            })
+            .insert(UiCameraConfig { show_ui: false })
+            .insert(player)
+            .insert(GameComponent);
+    }
+    commands
+        .spawn(Camera2dBundle {
+            camera: Camera {
+                order: Player::ALL.len() as isize,
+                ..default()
+            },
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::None,
+            },
+            ..default()
+        })
+        .insert(GameComponent);
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(15.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(GameComponent)
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "Left : Z, Q, S, D or first gamepad - Right : arrows or second gamepad",
+                    TextStyle {
+                        font_size: 30.0,
+                        color: TEXT_COLOR,
+                        ..default()
+                    },
+                ),
+                VersusText,
+            ));
+        });
+}
+
+/// Keep each camera on its half of the window.
+pub(crate) fn update_viewports(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut Camera, &Player)>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let size = UVec2::new(window.physical_width() / 2, window.physical_height());
+    if size.x == 0 || size.y == 0 {
+        return;
+    }
+    for (mut camera, player) in &mut cameras {
+        camera.viewport = Some(Viewport {
+            physical_position: UVec2::new(size.x * player.index() as u32, 0),
+            physical_size: size,
+            ..default()
+        });
+    }
+}
+
+pub(crate) fn handle_input(
+    keyboard: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut floors: Query<(&mut TiltInput, &Player)>,
+) {
+    for (mut tilt, player) in &mut floors {
+        *tilt = player.input(&keyboard, &gamepads, &axes);
+    }
+}
+
+pub(crate) fn detect_winner(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    goals: Query<(), With<Goal>>,
+    marbles: Query<&Player, With<Marble>>,
+    timer: Res<GameTimer>,
+    winner: Option<Res<Winner>>,
+) {
+    for collision_event in collision_events.iter() {
+        let CollisionEvent::Started(first, second, flags) = collision_event else {
+            continue;
+        };
+        if winner.is_some()
+            || !flags.intersects(CollisionEventFlags::SENSOR)
+            || !(goals.contains(*first) || goals.contains(*second))
+        {
+            continue;
+        }
+        for entity in [first, second] {
+            if let Ok(player) = marbles.get(*entity) {
+                info!(
+                    "Player {} wins in {:.2}s",
+                    player.index() + 1,
+                    timer.elapsed_secs()
+                );
+                commands.insert_resource(Winner {
+                    player: *player,
+                    timer: Timer::from_seconds(WINNER_DURATION, TimerMode::Once),
+                });
+                return;
+            }
+        }
+    }
+}
+
+/// Show the winner for a while, then go back to the menu.
+pub(crate) fn show_winner(
+    time: Res<Time>,
+    winner: Option<ResMut<Winner>>,
+    mut text: Query<&mut Text, With<VersusText>>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    let Some(mut winner) = winner else {
+        return;
+    };
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = format!("Player {} wins !", winner.player.index() + 1);
+    }
+    if winner.timer.tick(time.delta()).just_finished() {
+        state.set(AppState::Menu);
+    }
+}
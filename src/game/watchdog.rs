@@ -0,0 +1,68 @@
+//! Out of bounds watchdog
+//!
+//! Walls are very thin and the marble can sometimes go through them (or
+//! through the top collider) and fall forever. When the marble is found
+//! outside of the board, some diagnostic is logged and it is put back on
+//! the board.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use maze_generator::prelude::Coordinates;
+
+use crate::resources::CurrentMaze;
+
+use super::{
+    board::{Floor, Marble, MarbleLost, BORDER_HEIGHT, MARBLE_RADIUS},
+    hud::RunStats,
+};
+
+/// Last cell the marble was seen in, for the diagnostic.
+#[derive(Resource, Default)]
+pub(crate) struct LastCell(Option<Coordinates>);
+
+/// Forget the cell of the previous maze.
+pub(crate) fn setup_watchdog(mut commands: Commands) {
+    commands.insert_resource(LastCell::default());
+}
+
+pub(crate) fn watch_marble(
+    current_maze: Res<CurrentMaze>,
+    floor: Query<(&Transform, &GlobalTransform), With<Floor>>,
+    marble: Query<(&GlobalTransform, &Velocity), With<Marble>>,
+    mut stats: ResMut<RunStats>,
+    mut lost: EventWriter<MarbleLost>,
+    mut last_cell: ResMut<LastCell>,
+) {
+    let (Ok((floor, floor_global)), Ok((marble, velocity))) =
+        (floor.get_single(), marble.get_single())
+    else {
+        return;
+    };
+
+    // Check in the floor local space so the tilt doesn't matter
+    let position = floor_global
+        .affine()
+        .inverse()
+        .transform_point3(marble.translation());
+    let half_size = current_maze.board_size() / 2. + MARBLE_RADIUS;
+    let inside = position.x.abs() <= half_size
+        && position.z.abs() <= half_size
+        && (-MARBLE_RADIUS..=BORDER_HEIGHT + MARBLE_RADIUS).contains(&position.y);
+
+    if inside {
+        last_cell.0 = current_maze.cell_at(position).or(last_cell.0);
+        return;
+    }
+
+    let (tilt_x, _, tilt_z) = floor.rotation.to_euler(EulerRot::XYZ);
+    warn!(
+        "Marble escaped the board at {:?} (last cell {:?}), tilt ({:.3}, {:.3}), velocity {:?}",
+        position,
+        last_cell.0.map(|cell| (cell.x, cell.y)),
+        tilt_x,
+        tilt_z,
+        velocity.linvel
+    );
+    stats.faults += 1;
+    lost.send(MarbleLost);
+}
@@ -6,20 +6,40 @@ use bevy::diagnostic::*;
 use bevy::{log::LogPlugin, prelude::*, window::WindowTheme};
 #[cfg(feature = "inspector")]
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
+#[cfg(feature = "debug")]
 use bevy_rapier3d::prelude::*;
-use game::GamePlugin;
+use campaign::CampaignPlugin;
+use daily::DailyPlugin;
+use game::{GamePlugin, PhysicsPlugin};
 use menu::MenuPlugin;
-use resources::MazeConfig;
+use options::SavedOptions;
+use resources::{
+    BumperConfig, CameraConfig, CheckpointConfig, CoopConfig, DoorConfig, GameMode, GemConfig,
+    HintConfig, HoleConfig, MazeConfig, MinimapConfig, ObstacleConfig, PowerUpConfig,
+    TeleporterConfig, TileConfig,
+};
+use results::ResultsPlugin;
 
+mod campaign;
+mod daily;
 mod game;
 mod menu;
+mod options;
 mod resources;
+mod results;
+mod storage;
 
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 enum AppState {
     #[default]
     Menu,
     Game,
+    /// Transition state used to generate a new maze without going back to the menu.
+    Loading,
+    /// Choice of the campaign level to play.
+    LevelSelect,
+    /// Result and leaderboard at the end of a run.
+    Results,
 }
 
 fn main() -> Result<()> {
@@ -50,7 +70,7 @@ fn main() -> Result<()> {
                 ..default()
             })
             .set(log_plugin),
-        RapierPhysicsPlugin::<NoUserData>::default(),
+        PhysicsPlugin,
     ));
 
     #[cfg(feature = "inspector")]
@@ -69,8 +89,29 @@ fn main() -> Result<()> {
 
     app.add_state::<AppState>()
         .add_plugins(())
+        .insert_resource(GameMode::default())
         .insert_resource(MazeConfig::default())
-        .add_plugins((MenuPlugin, GamePlugin))
+        .insert_resource(HintConfig::default())
+        .insert_resource(MinimapConfig::default())
+        .insert_resource(CameraConfig::load())
+        .insert_resource(CoopConfig::default())
+        .insert_resource(HoleConfig::default())
+        .insert_resource(CheckpointConfig::default())
+        .insert_resource(GemConfig::default())
+        .insert_resource(DoorConfig::default())
+        .insert_resource(TileConfig::default())
+        .insert_resource(TeleporterConfig::default())
+        .insert_resource(ObstacleConfig::default())
+        .insert_resource(BumperConfig::default())
+        .insert_resource(PowerUpConfig::default())
+        .insert_resource(SavedOptions::default())
+        .add_plugins((
+            MenuPlugin,
+            GamePlugin,
+            CampaignPlugin,
+            DailyPlugin,
+            ResultsPlugin,
+        ))
         .run();
 
     Ok(())
@@ -1,24 +1,74 @@
 //! Menu state
 //!
 //! This is the start screen where we can select maze generation
-//! algorithm and size.
+//! algorithm and size, and enable optional game mechanics. The attract mode
+//! plays behind it.
+//!
+//! Game modes are laid out in rows, while options are on their own panel,
+//! so everything fits in a small window.
 //!
 //! This module is based on Bevy's [game menu](https://github.com/bevyengine/bevy/blob/main/examples/games/game_menu.rs)
 //! example.
 
 use bevy::prelude::*;
 
-use crate::AppState;
+use crate::{
+    daily::{self, DailyRecords},
+    game::{start_coop, start_versus, CoopRecords, Replay},
+    options::{GameOption, GameOptions},
+    resources::{Controls, CoopConfig, GameMode, MazeConfig},
+    AppState,
+};
 
 const TEXT_COLOR: Color = Color::rgb(0.1, 0.1, 0.1);
 const NORMAL_BUTTON: Color = Color::rgb(0.3, 0.3, 0.3);
 
+/// Everything shown in the menu, the attract mode behind it included.
 #[derive(Component)]
-struct MenuComponent;
+pub(crate) struct MenuComponent;
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy)]
 enum MenuButtonAction {
     Play,
+    Campaign,
+    Endless,
+    Daily,
+    Versus,
+    Coop,
+    /// Change the controls of a co-op player.
+    CoopControls(usize),
+    Replay,
+    /// Show the panel of the options.
+    Options,
+    /// Go back to the game modes.
+    Back,
+    Toggle(GameOption),
+    /// Change the speed of the moving obstacles.
+    Difficulty,
+}
+
+/// One of the panels of the menu, only one is shown at a time.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum MenuPanel {
+    Modes,
+    Options,
+}
+
+/// Text of a button that toggles an option.
+#[derive(Component)]
+struct ToggleText(GameOption);
+
+/// Text of the button that changes the obstacle speed.
+#[derive(Component)]
+struct DifficultyText;
+
+/// Text of a button that changes the controls of a co-op player.
+#[derive(Component)]
+struct ControlsText(usize);
+
+fn controls_label(player: usize, controls: Controls) -> String {
+    let axis = if player == 0 { "Pitch" } else { "Roll" };
+    format!("{axis} : {}", controls.label())
 }
 
 #[derive()]
@@ -26,17 +76,33 @@ pub(crate) struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::Menu), setup)
-            .add_systems(Update, handle_buttons.run_if(in_state(AppState::Menu)))
+        app.add_systems(OnEnter(AppState::Menu), (restore_options, setup).chain())
+            .add_systems(
+                Update,
+                (handle_buttons, update_toggle_texts, update_controls_texts)
+                    .chain()
+                    .run_if(in_state(AppState::Menu)),
+            )
             .add_systems(OnExit(AppState::Menu), remove::<MenuComponent>);
     }
 }
 
-fn setup(mut commands: Commands) {
+/// Runs with their own options are over, give the user theirs back.
+fn restore_options(mut options: GameOptions) {
+    options.restore_user_options();
+}
+
+fn setup(
+    mut commands: Commands,
+    options: GameOptions,
+    daily_records: Res<DailyRecords>,
+    coop_records: Res<CoopRecords>,
+    coop_config: Res<CoopConfig>,
+) {
     let button_style = Style {
         width: Val::Px(250.0),
         height: Val::Px(65.0),
-        margin: UiRect::all(Val::Px(20.0)),
+        margin: UiRect::all(Val::Px(10.0)),
         justify_content: JustifyContent::Center,
         align_items: AlignItems::Center,
         ..default()
@@ -46,11 +112,46 @@ fn setup(mut commands: Commands) {
         color: TEXT_COLOR,
         ..default()
     };
+    let toggle_style = Style {
+        width: Val::Px(250.0),
+        height: Val::Px(45.0),
+        margin: UiRect::all(Val::Px(5.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let toggle_text_style = TextStyle {
+        font_size: 30.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+    let panel_style = Style {
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        padding: UiRect::all(Val::Px(20.0)),
+        ..default()
+    };
+    // Buttons wrap in rows of three
+    let grid_style = Style {
+        width: Val::Px(810.0),
+        flex_wrap: FlexWrap::Wrap,
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let title_style = TextStyle {
+        font_size: 80.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+    let title_margin = Style {
+        margin: UiRect::all(Val::Px(20.0)),
+        ..default()
+    };
+    // Keep the menu readable over the attract mode
+    let panel_color = Color::rgba(1., 1., 1., 0.6);
 
-    commands
-        .spawn(Camera2dBundle::default())
-        .insert(MenuComponent);
-
+    // The camera is the one of the attract mode, see game::attract
     commands
         .spawn((
             NodeBundle {
@@ -66,33 +167,303 @@ fn setup(mut commands: Commands) {
         ))
         .with_children(|parent| {
             parent
-                .spawn(NodeBundle {
-                    style: Style {
-                        flex_direction: FlexDirection::Column,
-                        align_items: AlignItems::Center,
+                .spawn((
+                    NodeBundle {
+                        style: panel_style.clone(),
+                        background_color: panel_color.into(),
                         ..default()
                     },
-                    ..default()
-                })
+                    MenuPanel::Modes,
+                ))
                 .with_children(|parent| {
                     // Display the game name
+                    parent.spawn(
+                        TextBundle::from_section("Maze game", title_style.clone())
+                            .with_style(title_margin.clone()),
+                    );
+
+                    // Display buttons for each action available from the main menu
+                    parent
+                        .spawn(NodeBundle {
+                            style: grid_style.clone(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            // - new game
+                            parent
+                                .spawn((
+                                    ButtonBundle {
+                                        style: button_style.clone(),
+                                        background_color: NORMAL_BUTTON.into(),
+                                        ..default()
+                                    },
+                                    MenuButtonAction::Play,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "New Game",
+                                        button_text_style.clone(),
+                                    ));
+                                });
+
+                            // - campaign
+                            parent
+                                .spawn((
+                                    ButtonBundle {
+                                        style: button_style.clone(),
+                                        background_color: NORMAL_BUTTON.into(),
+                                        ..default()
+                                    },
+                                    MenuButtonAction::Campaign,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "Campaign",
+                                        button_text_style.clone(),
+                                    ));
+                                });
+
+                            // - endless
+                            parent
+                                .spawn((
+                                    ButtonBundle {
+                                        style: button_style.clone(),
+                                        background_color: NORMAL_BUTTON.into(),
+                                        ..default()
+                                    },
+                                    MenuButtonAction::Endless,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "Endless",
+                                        button_text_style.clone(),
+                                    ));
+                                });
+
+                            // - daily challenge, with the streak and today's best score
+                            let today = daily::today();
+                            let daily_status = match daily_records.best(today) {
+                                Some(score) => format!(
+                                    "Streak : {} - Best : {score}",
+                                    daily_records.streak(today)
+                                ),
+                                None => format!("Streak : {}", daily_records.streak(today)),
+                            };
+                            parent
+                                .spawn((
+                                    ButtonBundle {
+                                        style: Style {
+                                            height: Val::Px(90.0),
+                                            flex_direction: FlexDirection::Column,
+                                            ..button_style.clone()
+                                        },
+                                        background_color: NORMAL_BUTTON.into(),
+                                        ..default()
+                                    },
+                                    MenuButtonAction::Daily,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "Daily challenge",
+                                        button_text_style.clone(),
+                                    ));
+                                    parent.spawn(TextBundle::from_section(
+                                        daily_status,
+                                        toggle_text_style.clone(),
+                                    ));
+                                });
+
+                            // - race between two players
+                            parent
+                                .spawn((
+                                    ButtonBundle {
+                                        style: button_style.clone(),
+                                        background_color: NORMAL_BUTTON.into(),
+                                        ..default()
+                                    },
+                                    MenuButtonAction::Versus,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "Versus",
+                                        button_text_style.clone(),
+                                    ));
+                                });
+
+                            // - co-op, with the best score
+                            let coop_status = match coop_records.best() {
+                                Some(score) => format!("Best : {score}"),
+                                None => "No run yet".to_string(),
+                            };
+                            parent
+                                .spawn((
+                                    ButtonBundle {
+                                        style: Style {
+                                            height: Val::Px(90.0),
+                                            flex_direction: FlexDirection::Column,
+                                            ..button_style.clone()
+                                        },
+                                        background_color: NORMAL_BUTTON.into(),
+                                        ..default()
+                                    },
+                                    MenuButtonAction::Coop,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "Co-op",
+                                        button_text_style.clone(),
+                                    ));
+                                    parent.spawn(TextBundle::from_section(
+                                        coop_status,
+                                        toggle_text_style.clone(),
+                                    ));
+                                });
+
+                            // - last saved replay
+                            parent
+                                .spawn((
+                                    ButtonBundle {
+                                        style: button_style.clone(),
+                                        background_color: NORMAL_BUTTON.into(),
+                                        ..default()
+                                    },
+                                    MenuButtonAction::Replay,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "Watch replay",
+                                        button_text_style.clone(),
+                                    ));
+                                });
+
+                            // - game options
+                            parent
+                                .spawn((
+                                    ButtonBundle {
+                                        style: button_style.clone(),
+                                        background_color: NORMAL_BUTTON.into(),
+                                        ..default()
+                                    },
+                                    MenuButtonAction::Options,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "Options",
+                                        button_text_style.clone(),
+                                    ));
+                                });
+                        });
+
+                    // - ghost import, see game::ghost
+                    #[cfg(not(target_arch = "wasm32"))]
+                    let ghost_note = "Drop a ghost file here to race it";
+                    // Browsers don't give the path of dropped files
+                    #[cfg(target_arch = "wasm32")]
+                    let ghost_note = "Ghost files can only be imported in the desktop version";
                     parent.spawn(
                         TextBundle::from_section(
-                            "Maze game",
+                            ghost_note,
                             TextStyle {
-                                font_size: 80.0,
+                                font_size: 20.0,
                                 color: TEXT_COLOR,
                                 ..default()
                             },
                         )
                         .with_style(Style {
-                            margin: UiRect::all(Val::Px(50.0)),
+                            margin: UiRect::all(Val::Px(10.0)),
                             ..default()
                         }),
                     );
+                });
+
+            // Options, hidden until asked for
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            display: Display::None,
+                            ..panel_style.clone()
+                        },
+                        background_color: panel_color.into(),
+                        ..default()
+                    },
+                    MenuPanel::Options,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section("Options", title_style.clone())
+                            .with_style(title_margin.clone()),
+                    );
+
+                    parent
+                        .spawn(NodeBundle {
+                            style: grid_style.clone(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            // - game mechanics
+                            for option in GameOption::ALL {
+                                parent
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: toggle_style.clone(),
+                                            background_color: NORMAL_BUTTON.into(),
+                                            ..default()
+                                        },
+                                        MenuButtonAction::Toggle(option),
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            TextBundle::from_section(
+                                                options.label(option),
+                                                toggle_text_style.clone(),
+                                            ),
+                                            ToggleText(option),
+                                        ));
+                                    });
+                            }
+                            parent
+                                .spawn((
+                                    ButtonBundle {
+                                        style: toggle_style.clone(),
+                                        background_color: NORMAL_BUTTON.into(),
+                                        ..default()
+                                    },
+                                    MenuButtonAction::Difficulty,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn((
+                                        TextBundle::from_section(
+                                            options.difficulty_label(),
+                                            toggle_text_style.clone(),
+                                        ),
+                                        DifficultyText,
+                                    ));
+                                });
+
+                            // - controls of each co-op player
+                            for (player, controls) in coop_config.controls.into_iter().enumerate() {
+                                parent
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: toggle_style.clone(),
+                                            background_color: NORMAL_BUTTON.into(),
+                                            ..default()
+                                        },
+                                        MenuButtonAction::CoopControls(player),
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            TextBundle::from_section(
+                                                controls_label(player, controls),
+                                                toggle_text_style.clone(),
+                                            ),
+                                            ControlsText(player),
+                                        ));
+                                    });
+                            }
+                        });
 
-                    // Display three buttons for each action available from the main menu:
-                    // - new game
                     parent
                         .spawn((
                             ButtonBundle {
@@ -100,35 +471,115 @@ fn setup(mut commands: Commands) {
                                 background_color: NORMAL_BUTTON.into(),
                                 ..default()
                             },
-                            MenuButtonAction::Play,
+                            MenuButtonAction::Back,
                         ))
                         .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section(
-                                "New Game",
-                                button_text_style.clone(),
-                            ));
+                            parent
+                                .spawn(TextBundle::from_section("Back", button_text_style.clone()));
                         });
                 });
         });
 }
 
+#[allow(clippy::too_many_arguments)]
 #[allow(clippy::type_complexity)]
 fn handle_buttons(
+    mut commands: Commands,
     interaction_query: Query<
         (&Interaction, &MenuButtonAction),
         (Changed<Interaction>, With<Button>),
     >,
     mut state: ResMut<NextState<AppState>>,
+    mut options: GameOptions,
+    mut mode: ResMut<GameMode>,
+    mut maze_config: ResMut<MazeConfig>,
+    mut coop_config: ResMut<CoopConfig>,
+    mut panels: Query<(&MenuPanel, &mut Style)>,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
             match menu_button_action {
-                MenuButtonAction::Play => state.set(AppState::Game),
+                // Free games use the default maze
+                MenuButtonAction::Play => {
+                    *mode = GameMode::Free;
+                    *maze_config = MazeConfig::default();
+                    state.set(AppState::Game);
+                }
+                MenuButtonAction::Campaign => state.set(AppState::LevelSelect),
+                MenuButtonAction::Endless => {
+                    *mode = GameMode::Endless { stage: 0 };
+                    state.set(AppState::Game);
+                }
+                MenuButtonAction::Versus => {
+                    start_versus(&mut mode, &mut maze_config, &mut options);
+                    state.set(AppState::Game);
+                }
+                MenuButtonAction::Coop => {
+                    start_coop(&mut mode, &mut maze_config);
+                    state.set(AppState::Game);
+                }
+                MenuButtonAction::CoopControls(player) => {
+                    coop_config.controls[*player] = coop_config.controls[*player].next();
+                }
+                MenuButtonAction::Replay => match Replay::load_saved() {
+                    Some(replay) => {
+                        replay.watch(&mut commands, &mut mode, &mut maze_config, &mut options);
+                        state.set(AppState::Game);
+                    }
+                    None => info!("No replay saved yet"),
+                },
+                MenuButtonAction::Daily => {
+                    daily::load(daily::today(), &mut mode, &mut maze_config, &mut options);
+                    state.set(AppState::Game);
+                }
+                MenuButtonAction::Options => show_panel(&mut panels, MenuPanel::Options),
+                MenuButtonAction::Back => show_panel(&mut panels, MenuPanel::Modes),
+                MenuButtonAction::Toggle(option) => options.toggle(*option),
+                MenuButtonAction::Difficulty => {
+                    let difficulty = options.difficulty().next();
+                    options.set_difficulty(difficulty);
+                }
             }
         }
     }
 }
 
+fn show_panel(panels: &mut Query<(&MenuPanel, &mut Style)>, shown: MenuPanel) {
+    for (panel, mut style) in panels {
+        style.display = if *panel == shown {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_toggle_texts(
+    options: GameOptions,
+    mut texts: Query<(&ToggleText, &mut Text), Without<DifficultyText>>,
+    mut difficulty_text: Query<&mut Text, With<DifficultyText>>,
+) {
+    if !options.is_changed() {
+        return;
+    }
+    for (ToggleText(option), mut text) in &mut texts {
+        text.sections[0].value = options.label(*option);
+    }
+    if let Ok(mut text) = difficulty_text.get_single_mut() {
+        text.sections[0].value = options.difficulty_label();
+    }
+}
+
+fn update_controls_texts(config: Res<CoopConfig>, mut texts: Query<(&ControlsText, &mut Text)>) {
+    if !config.is_changed() {
+        return;
+    }
+    for (ControlsText(player), mut text) in &mut texts {
+        text.sections[0].value = controls_label(*player, config.controls[*player]);
+    }
+}
+
 fn remove<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
     for entity in &to_despawn {
         commands.entity(entity).despawn_recursive();
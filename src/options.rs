@@ -0,0 +1,212 @@
+//! Optional game mechanics
+//!
+//! Each mechanic has its own settings resource, this gathers their enable
+//! flags so the menu and the campaign levels can switch them.
+//!
+//! Runs coming with their own options (campaign levels, daily challenges,
+//! replays, imported ghosts, races) keep the options of the user first, they
+//! are restored when back in the menu.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::resources::{
+    BumperConfig, CheckpointConfig, Difficulty, DoorConfig, GemConfig, HoleConfig, ObstacleConfig,
+    PowerUpConfig, TeleporterConfig, TileConfig,
+};
+
+/// Optional game mechanics that can be enabled.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) enum GameOption {
+    Holes,
+    Checkpoints,
+    Gems,
+    Doors,
+    Tiles,
+    Teleporters,
+    Obstacles,
+    Bumpers,
+    PowerUps,
+}
+
+impl GameOption {
+    pub(crate) const ALL: [GameOption; 9] = [
+        GameOption::Holes,
+        GameOption::Checkpoints,
+        GameOption::Gems,
+        GameOption::Doors,
+        GameOption::Tiles,
+        GameOption::Teleporters,
+        GameOption::Obstacles,
+        GameOption::Bumpers,
+        GameOption::PowerUps,
+    ];
+}
+
+/// Settings chosen by the user, while a run uses its own ones.
+#[derive(Clone, Copy)]
+struct UserOptions {
+    holes: HoleConfig,
+    checkpoints: CheckpointConfig,
+    gems: GemConfig,
+    doors: DoorConfig,
+    tiles: TileConfig,
+    teleporters: TeleporterConfig,
+    obstacles: ObstacleConfig,
+    bumpers: BumperConfig,
+    power_ups: PowerUpConfig,
+}
+
+/// Options of the user to restore, see [GameOptions::keep_user_options].
+#[derive(Resource, Default)]
+pub(crate) struct SavedOptions(Option<UserOptions>);
+
+/// Settings of all the [GameOption].
+#[derive(SystemParam)]
+pub(crate) struct GameOptions<'w> {
+    holes: ResMut<'w, HoleConfig>,
+    checkpoints: ResMut<'w, CheckpointConfig>,
+    gems: ResMut<'w, GemConfig>,
+    doors: ResMut<'w, DoorConfig>,
+    tiles: ResMut<'w, TileConfig>,
+    teleporters: ResMut<'w, TeleporterConfig>,
+    obstacles: ResMut<'w, ObstacleConfig>,
+    bumpers: ResMut<'w, BumperConfig>,
+    power_ups: ResMut<'w, PowerUpConfig>,
+    saved: ResMut<'w, SavedOptions>,
+}
+
+impl GameOptions<'_> {
+    pub(crate) fn enabled(&self, option: GameOption) -> bool {
+        match option {
+            GameOption::Holes => self.holes.enabled,
+            GameOption::Checkpoints => self.checkpoints.enabled,
+            GameOption::Gems => self.gems.enabled,
+            GameOption::Doors => self.doors.enabled,
+            GameOption::Tiles => self.tiles.enabled,
+            GameOption::Teleporters => self.teleporters.enabled,
+            GameOption::Obstacles => self.obstacles.enabled,
+            GameOption::Bumpers => self.bumpers.enabled,
+            GameOption::PowerUps => self.power_ups.enabled,
+        }
+    }
+
+    pub(crate) fn set(&mut self, option: GameOption, enabled: bool) {
+        match option {
+            GameOption::Holes => self.holes.enabled = enabled,
+            GameOption::Checkpoints => self.checkpoints.enabled = enabled,
+            GameOption::Gems => self.gems.enabled = enabled,
+            GameOption::Doors => self.doors.enabled = enabled,
+            GameOption::Tiles => self.tiles.enabled = enabled,
+            GameOption::Teleporters => self.teleporters.enabled = enabled,
+            GameOption::Obstacles => self.obstacles.enabled = enabled,
+            GameOption::Bumpers => self.bumpers.enabled = enabled,
+            GameOption::PowerUps => self.power_ups.enabled = enabled,
+        }
+    }
+
+    pub(crate) fn toggle(&mut self, option: GameOption) {
+        match option {
+            GameOption::Holes => self.holes.enabled = !self.holes.enabled,
+            GameOption::Checkpoints => self.checkpoints.enabled = !self.checkpoints.enabled,
+            GameOption::Gems => self.gems.enabled = !self.gems.enabled,
+            GameOption::Doors => self.doors.enabled = !self.doors.enabled,
+            GameOption::Tiles => self.tiles.enabled = !self.tiles.enabled,
+            GameOption::Teleporters => self.teleporters.enabled = !self.teleporters.enabled,
+            GameOption::Obstacles => self.obstacles.enabled = !self.obstacles.enabled,
+            GameOption::Bumpers => self.bumpers.enabled = !self.bumpers.enabled,
+            GameOption::PowerUps => self.power_ups.enabled = !self.power_ups.enabled,
+        }
+    }
+
+    /// The enabled options, in the [GameOption::ALL] order.
+    pub(crate) fn enabled_options(&self) -> Vec<GameOption> {
+        GameOption::ALL
+            .into_iter()
+            .filter(|option| self.enabled(*option))
+            .collect()
+    }
+
+    pub(crate) fn is_changed(&self) -> bool {
+        self.holes.is_changed()
+            || self.checkpoints.is_changed()
+            || self.gems.is_changed()
+            || self.doors.is_changed()
+            || self.tiles.is_changed()
+            || self.teleporters.is_changed()
+            || self.obstacles.is_changed()
+            || self.bumpers.is_changed()
+            || self.power_ups.is_changed()
+    }
+
+    pub(crate) fn label(&self, option: GameOption) -> String {
+        let name = match option {
+            GameOption::Holes => "Holes",
+            GameOption::Checkpoints => "Checkpoints",
+            GameOption::Gems => "Gems",
+            GameOption::Doors => "Keys and doors",
+            GameOption::Tiles => "Special tiles",
+            GameOption::Teleporters => "Teleporters",
+            GameOption::Obstacles => "Moving obstacles",
+            GameOption::Bumpers => "Bumpers and wells",
+            GameOption::PowerUps => "Power-ups",
+        };
+        let state = if self.enabled(option) { "on" } else { "off" };
+        format!("{name} : {state}")
+    }
+
+    /// Difficulty of the moving obstacles.
+    pub(crate) fn difficulty(&self) -> Difficulty {
+        self.obstacles.difficulty
+    }
+
+    pub(crate) fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.obstacles.difficulty = difficulty;
+    }
+
+    pub(crate) fn difficulty_label(&self) -> String {
+        format!("Obstacle speed : {:?}", self.difficulty())
+    }
+
+    /// Number of bumpers and speed change they give to the marble.
+    pub(crate) fn set_bumpers(&mut self, bumpers: usize, kick: f32) {
+        self.bumpers.bumpers = bumpers;
+        self.bumpers.bumper_kick = kick;
+    }
+
+    /// Keep the options of the user before a run changes them. Only the
+    /// first call counts until they are restored, so the following levels of
+    /// a run don't overwrite them.
+    pub(crate) fn keep_user_options(&mut self) {
+        if self.saved.0.is_some() {
+            return;
+        }
+        self.saved.0 = Some(UserOptions {
+            holes: *self.holes,
+            checkpoints: *self.checkpoints,
+            gems: *self.gems,
+            doors: *self.doors,
+            tiles: *self.tiles,
+            teleporters: *self.teleporters,
+            obstacles: *self.obstacles,
+            bumpers: *self.bumpers,
+            power_ups: *self.power_ups,
+        });
+    }
+
+    /// Put back the options kept by [GameOptions::keep_user_options], if any.
+    pub(crate) fn restore_user_options(&mut self) {
+        let Some(saved) = self.saved.0.take() else {
+            return;
+        };
+        *self.holes = saved.holes;
+        *self.checkpoints = saved.checkpoints;
+        *self.gems = saved.gems;
+        *self.doors = saved.doors;
+        *self.tiles = saved.tiles;
+        *self.teleporters = saved.teleporters;
+        *self.obstacles = saved.obstacles;
+        *self.bumpers = saved.bumpers;
+        *self.power_ups = saved.power_ups;
+    }
+}
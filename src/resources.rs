@@ -1,13 +1,20 @@
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+
 use bevy::prelude::*;
 use maze_generator::{
     ellers_algorithm::EllersGenerator,
     growing_tree::GrowingTreeGenerator,
-    prelude::{Generator, Maze},
+    prelude::{Coordinates, Direction, FieldType, Generator, Maze},
     prims_algorithm::PrimsGenerator,
     recursive_backtracking::RbGenerator,
 };
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) enum Algorithm {
     Ellers,
     #[default]
@@ -16,14 +23,15 @@ pub(crate) enum Algorithm {
     RecursiveBacktracking,
 }
 
-#[derive(Resource, Clone, Copy)]
+#[derive(Resource, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct MazeConfig {
     algorithm: Algorithm,
     height: i32,
     width: i32,
+    /// Seed of the generator, `None` for a random maze.
+    seed: Option<u64>,
 }
 
-/*
 impl MazeConfig {
     pub(crate) fn set_algorithm(&mut self, algorithm: Algorithm) {
         self.algorithm = algorithm;
@@ -36,8 +44,51 @@ impl MazeConfig {
     pub(crate) fn set_width(&mut self, width: i32) {
         self.width = width;
     }
+
+    pub(crate) fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    /// Whether the seed is configured, the maze being the same each time.
+    pub(crate) fn is_seeded(&self) -> bool {
+        self.seed.is_some()
+    }
+
+    /// Short text identifying the maze, usable in a file name.
+    pub(crate) fn id(&self) -> String {
+        format!(
+            "{:?}-{}x{}-{}",
+            self.algorithm,
+            self.width,
+            self.height,
+            self.seed.unwrap_or_default()
+        )
+    }
+
+    /// Same settings with a seed drawn at random if none is configured, so
+    /// the exact same maze can be generated again.
+    pub(crate) fn with_random_seed(&self) -> anyhow::Result<Self> {
+        if self.seed.is_some() {
+            return Ok(*self);
+        }
+
+        // Seeds using getrandom crate with JS feature enable
+        // for wasm in JS environment to work.
+        #[cfg(feature = "js")]
+        let seed = {
+            let mut buf = [0u8; 8];
+            getrandom::getrandom(&mut buf).map_err(|err| anyhow::Error::msg(err.to_string()))?;
+            u64::from_le_bytes(buf)
+        };
+        #[cfg(not(feature = "js"))]
+        let seed = rand::random();
+
+        Ok(Self {
+            seed: Some(seed),
+            ..*self
+        })
+    }
 }
-*/
 
 impl Default for MazeConfig {
     fn default() -> Self {
@@ -45,6 +96,7 @@ impl Default for MazeConfig {
             algorithm: Default::default(),
             height: 15,
             width: 15,
+            seed: None,
         }
     }
 }
@@ -53,17 +105,13 @@ impl TryFrom<&MazeConfig> for Maze {
     type Error = anyhow::Error;
 
     fn try_from(value: &MazeConfig) -> Result<Self, Self::Error> {
-        // Seeds using getrandom crate with JS feature enable
-        // for wasm in JS environment to work.
-        #[cfg(feature = "js")]
-        let seed = {
+        // Expand the seed, so the maze is the same on every platform. Use
+        // MazeConfig::with_random_seed first to get a random maze.
+        let seed = value.seed.map(|seed| {
             let mut buf = [0u8; 32];
-            getrandom::getrandom(&mut buf).map_err(|err| anyhow::Error::msg(err.to_string()))?;
-            Some(buf)
-        };
-        // Otherwise don't bother
-        #[cfg(not(feature = "js"))]
-        let seed = None;
+            buf[..8].copy_from_slice(&seed.to_le_bytes());
+            buf
+        });
 
         match value.algorithm {
             Algorithm::Ellers => EllersGenerator::new(seed).generate(value.width, value.height),
@@ -77,3 +125,696 @@ impl TryFrom<&MazeConfig> for Maze {
         }
     }
 }
+
+/// What the game being played is part of.
+#[derive(Resource, Default, Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum GameMode {
+    /// A single maze, with the menu settings.
+    #[default]
+    Free,
+    /// A level of the campaign, by index.
+    Campaign(usize),
+    /// Chained mazes, `stage` being the number of mazes already finished.
+    Endless { stage: u32 },
+    /// The daily challenge, by days since the Unix epoch.
+    Daily(u64),
+    /// A recorded run played back.
+    Replay,
+    /// Two players racing on the same maze, side by side.
+    Versus,
+    /// Two players sharing the board, each one tilting it around an axis.
+    Coop,
+}
+
+/// Hint settings.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct HintConfig {
+    /// Number of hints available for a run.
+    pub(crate) budget: u32,
+    /// Seconds added to the timer each time a hint is used.
+    pub(crate) penalty: f32,
+    /// How long, in seconds, a hint stays visible.
+    pub(crate) duration: f32,
+    /// Number of cells shown from the marble, `None` to show the whole path.
+    pub(crate) length: Option<usize>,
+}
+
+impl Default for HintConfig {
+    fn default() -> Self {
+        Self {
+            budget: 3,
+            penalty: 10.,
+            duration: 3.,
+            length: Some(6),
+        }
+    }
+}
+
+/// Minimap settings.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct MinimapConfig {
+    pub(crate) visible: bool,
+    /// Only show cells the marble went through.
+    pub(crate) fog_of_war: bool,
+}
+
+impl Default for MinimapConfig {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            fog_of_war: false,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) enum CameraMode {
+    /// Looking at the board from a fixed point.
+    #[default]
+    Fixed,
+    /// Following the marble.
+    Follow,
+    /// Orthographic view from above.
+    TopDown,
+    /// Turning around the board.
+    Orbit,
+}
+
+impl CameraMode {
+    pub(crate) fn next(self) -> Self {
+        match self {
+            CameraMode::Fixed => CameraMode::Follow,
+            CameraMode::Follow => CameraMode::TopDown,
+            CameraMode::TopDown => CameraMode::Orbit,
+            CameraMode::Orbit => CameraMode::Fixed,
+        }
+    }
+}
+
+const CAMERA_SAVE_KEY: &str = "camera";
+
+/// Camera settings, saved from one session to the next.
+#[derive(Resource, Default, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct CameraConfig {
+    pub(crate) mode: CameraMode,
+}
+
+impl CameraConfig {
+    /// The saved settings, or the default ones.
+    pub(crate) fn load() -> Self {
+        storage::load(CAMERA_SAVE_KEY).unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self) {
+        storage::save(CAMERA_SAVE_KEY, self);
+    }
+}
+
+/// Keys or gamepad a player tilts the board with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Controls {
+    /// Z, Q, S and D.
+    LeftKeys,
+    /// The arrows.
+    RightKeys,
+    /// A gamepad, by connection order.
+    Gamepad(usize),
+}
+
+impl Controls {
+    /// Number of gamepads that can be chosen.
+    const GAMEPADS: usize = 4;
+
+    pub(crate) fn next(self) -> Self {
+        match self {
+            Controls::LeftKeys => Controls::RightKeys,
+            Controls::RightKeys => Controls::Gamepad(0),
+            Controls::Gamepad(index) if index + 1 < Self::GAMEPADS => Controls::Gamepad(index + 1),
+            Controls::Gamepad(_) => Controls::LeftKeys,
+        }
+    }
+
+    pub(crate) fn label(self) -> String {
+        match self {
+            Controls::LeftKeys => "Z, Q, S, D".to_string(),
+            Controls::RightKeys => "arrows".to_string(),
+            Controls::Gamepad(index) => format!("gamepad {}", index + 1),
+        }
+    }
+}
+
+/// Co-op settings.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct CoopConfig {
+    /// Controls of the player tilting the board around `x` (pitch), then of
+    /// the one tilting it around `z` (roll).
+    pub(crate) controls: [Controls; 2],
+}
+
+impl Default for CoopConfig {
+    fn default() -> Self {
+        Self {
+            controls: [Controls::LeftKeys, Controls::RightKeys],
+        }
+    }
+}
+
+/// Holes in the board, like the wooden labyrinth game.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct HoleConfig {
+    pub(crate) enabled: bool,
+    /// Ratio of the candidate cells (dead ends and cells next to the
+    /// solution) that get a hole.
+    pub(crate) density: f32,
+    /// Number of times the marble can fall before the game is over.
+    pub(crate) lives: u32,
+}
+
+impl Default for HoleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            density: 0.3,
+            lives: 3,
+        }
+    }
+}
+
+/// Checkpoints along the way to the goal, where the marble is put back
+/// when it is lost.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct CheckpointConfig {
+    pub(crate) enabled: bool,
+    /// Number of checkpoints, evenly spread on the solution path.
+    pub(crate) count: usize,
+}
+
+impl Default for CheckpointConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            count: 3,
+        }
+    }
+}
+
+/// Gems to collect in dead ends before reaching the goal.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct GemConfig {
+    pub(crate) enabled: bool,
+    /// Number of gems, limited by the number of dead ends.
+    pub(crate) count: u32,
+    /// Number of gems needed to finish, `None` for all of them.
+    pub(crate) required: Option<u32>,
+    /// Score bonus when all gems are collected.
+    pub(crate) bonus: u32,
+}
+
+impl Default for GemConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            count: 5,
+            required: None,
+            bonus: 2000,
+        }
+    }
+}
+
+/// Locked doors on the way to the goal, opened by keys.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct DoorConfig {
+    pub(crate) enabled: bool,
+    /// Number of doors, each one with its key.
+    pub(crate) count: usize,
+}
+
+impl Default for DoorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            count: 2,
+        }
+    }
+}
+
+/// Pairs of teleporter pads, each one sending the marble to the other one.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct TeleporterConfig {
+    pub(crate) enabled: bool,
+    /// Number of pairs of pads.
+    pub(crate) pairs: usize,
+    /// Keep the marble velocity when teleported, otherwise it is stopped.
+    pub(crate) keep_velocity: bool,
+    /// Seconds before a pad can be used again, so the marble doesn't go back
+    /// and forth between two pads.
+    pub(crate) cooldown: f32,
+}
+
+impl Default for TeleporterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            pairs: 2,
+            keep_velocity: true,
+            cooldown: 1.,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub(crate) fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    /// Multiplier applied to the speed of moving elements.
+    pub(crate) fn speed_factor(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 1.,
+            Difficulty::Hard => 1.5,
+        }
+    }
+}
+
+/// Moving obstacles : sliding blocks in long corridors and rotating bars in
+/// junctions.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct ObstacleConfig {
+    pub(crate) enabled: bool,
+    /// Maximum number of sliding blocks, limited by the number of long
+    /// corridors.
+    pub(crate) blocks: usize,
+    /// Maximum number of bars, limited by the number of junctions.
+    pub(crate) bars: usize,
+    pub(crate) difficulty: Difficulty,
+}
+
+impl Default for ObstacleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            blocks: 3,
+            bars: 3,
+            difficulty: Difficulty::default(),
+        }
+    }
+}
+
+/// Pinball-like elements : bumpers kicking the marble back and wells
+/// attracting or repelling it.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct BumperConfig {
+    pub(crate) enabled: bool,
+    /// Maximum number of bumpers, they are put at the end of dead ends.
+    pub(crate) bumpers: usize,
+    /// Speed change given by a bumper to the marble, away from its center.
+    pub(crate) bumper_kick: f32,
+    /// Maximum number of wells, half of them attracting the marble and the
+    /// other half repelling it.
+    pub(crate) wells: usize,
+    /// Acceleration given by a well at its center.
+    pub(crate) well_strength: f32,
+}
+
+impl Default for BumperConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bumpers: 4,
+            bumper_kick: 1.,
+            wells: 2,
+            well_strength: 1.5,
+        }
+    }
+}
+
+/// Power-ups to pick up, each one giving a temporary effect.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct PowerUpConfig {
+    pub(crate) enabled: bool,
+    /// Number of power-ups in the maze.
+    pub(crate) count: usize,
+    /// How long, in seconds, an effect lasts.
+    pub(crate) duration: f32,
+    /// Speed of the physics and of the tilt while in slow motion.
+    pub(crate) slow_motion_scale: f32,
+    /// Acceleration toward the goal given by the magnet.
+    pub(crate) magnet_strength: f32,
+}
+
+impl Default for PowerUpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            count: 3,
+            duration: 5.,
+            slow_motion_scale: 0.5,
+            magnet_strength: 1.,
+        }
+    }
+}
+
+/// Special floor tiles changing how the marble moves.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct TileConfig {
+    pub(crate) enabled: bool,
+    /// Ratio of the free cells (no hole nor item) that get a special tile.
+    pub(crate) density: f32,
+}
+
+impl Default for TileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            density: 0.15,
+        }
+    }
+}
+
+/// Offset of the neighbour cell in the given direction.
+///
+/// Lines grow toward south, so north is `-1` on the `y` axis.
+fn offset(direction: &Direction) -> (i32, i32) {
+    match direction {
+        Direction::North => (0, -1),
+        Direction::East => (1, 0),
+        Direction::South => (0, 1),
+        Direction::West => (-1, 0),
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// 64 bits FNV-1a hash. Unlike the standard library hashers, its values don't
+/// change between Rust versions or platforms, so a maze layout always gives
+/// the same items, on every platform.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// The maze currently played.
+///
+/// It is generated when entering the game from [MazeConfig] and kept
+/// until the game is left, so any system can query its layout.
+///
+/// Positions are expressed in the floor local space : the board is centered on
+/// the origin, columns grow along the `x` axis and lines along the `z` axis.
+#[derive(Resource)]
+pub(crate) struct CurrentMaze {
+    maze: Maze,
+    /// Settings the maze was generated with, seed included.
+    config: MazeConfig,
+    board_size: f32,
+    start: Coordinates,
+    goal: Coordinates,
+    /// Hash of the layout, used to seed [CurrentMaze::rng].
+    layout_hash: u64,
+}
+
+impl CurrentMaze {
+    pub(crate) const DIRECTIONS: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// Wrap `maze`, which will be drawn on a square board of `board_size` side.
+    pub(crate) fn new(maze: Maze, config: MazeConfig, board_size: f32) -> Self {
+        let (width, height) = maze.size;
+        let mut start = Coordinates::new(0, 0);
+        let mut goal = Coordinates::new(width - 1, height - 1);
+        let mut hasher = Fnv1a::new();
+        hasher.write(&width.to_le_bytes());
+        hasher.write(&height.to_le_bytes());
+        for line in 0..height {
+            for column in 0..width {
+                let coordinates = Coordinates::new(column, line);
+                if let Some(field) = maze.get_field(&coordinates) {
+                    match field.field_type {
+                        FieldType::Start => start = coordinates,
+                        FieldType::Goal => goal = coordinates,
+                        FieldType::Normal => (),
+                    }
+                    for direction in Self::DIRECTIONS.iter() {
+                        hasher.write(&[field.has_passage(direction) as u8]);
+                    }
+                }
+            }
+        }
+
+        Self {
+            maze,
+            config,
+            board_size,
+            start,
+            goal,
+            layout_hash: hasher.finish(),
+        }
+    }
+
+    pub(crate) fn maze(&self) -> &Maze {
+        &self.maze
+    }
+
+    pub(crate) fn config(&self) -> &MazeConfig {
+        &self.config
+    }
+
+    /// Number of columns and lines.
+    pub(crate) fn size(&self) -> (i32, i32) {
+        self.maze.size
+    }
+
+    pub(crate) fn board_size(&self) -> f32 {
+        self.board_size
+    }
+
+    /// Size of a cell along `x` and `z` axis.
+    pub(crate) fn cell_size(&self) -> Vec2 {
+        let (width, height) = self.maze.size;
+        Vec2::new(
+            self.board_size / width as f32,
+            self.board_size / height as f32,
+        )
+    }
+
+    pub(crate) fn start(&self) -> Coordinates {
+        self.start
+    }
+
+    pub(crate) fn goal(&self) -> Coordinates {
+        self.goal
+    }
+
+    /// All cells, line by line.
+    pub(crate) fn cells(&self) -> impl Iterator<Item = Coordinates> {
+        let (width, height) = self.maze.size;
+        (0..height)
+            .flat_map(move |line| (0..width).map(move |column| Coordinates::new(column, line)))
+    }
+
+    /// Random generator that only depends on the maze layout, so the same maze
+    /// always gets the same elements. Use a different `salt` for each usage.
+    pub(crate) fn rng(&self, salt: u64) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(self.layout_hash ^ salt)
+    }
+
+    pub(crate) fn contains(&self, coordinates: Coordinates) -> bool {
+        let (width, height) = self.maze.size;
+        (0..width).contains(&coordinates.x) && (0..height).contains(&coordinates.y)
+    }
+
+    /// Center of the cell, on the floor.
+    pub(crate) fn cell_center(&self, coordinates: Coordinates) -> Vec3 {
+        let cell_size = self.cell_size();
+        Vec3::new(
+            (coordinates.x as f32 + 0.5) * cell_size.x - self.board_size / 2.,
+            0.,
+            (coordinates.y as f32 + 0.5) * cell_size.y - self.board_size / 2.,
+        )
+    }
+
+    /// Cell containing `position`, if it is on the board.
+    pub(crate) fn cell_at(&self, position: Vec3) -> Option<Coordinates> {
+        let cell_size = self.cell_size();
+        let column = ((position.x + self.board_size / 2.) / cell_size.x).floor() as i32;
+        let line = ((position.z + self.board_size / 2.) / cell_size.y).floor() as i32;
+        let coordinates = Coordinates::new(column, line);
+        self.contains(coordinates).then_some(coordinates)
+    }
+
+    /// Same as [CurrentMaze::cell_at] but with a world position, `floor` being
+    /// the global transform of the (possibly tilted) floor.
+    pub(crate) fn cell_at_world(
+        &self,
+        floor: &GlobalTransform,
+        position: Vec3,
+    ) -> Option<Coordinates> {
+        self.cell_at(floor.affine().inverse().transform_point3(position))
+    }
+
+    /// Cells with only one way in or out, except start and goal.
+    pub(crate) fn dead_ends(&self) -> Vec<Coordinates> {
+        self.cells()
+            .filter(|cell| *cell != self.start && *cell != self.goal)
+            .filter(|cell| self.neighbours(*cell).len() == 1)
+            .collect()
+    }
+
+    /// Cells directly reachable from `coordinates`, i.e. without a wall between them.
+    pub(crate) fn neighbours(&self, coordinates: Coordinates) -> Vec<Coordinates> {
+        let Some(field) = self.maze.get_field(&coordinates) else {
+            return Vec::new();
+        };
+        Self::DIRECTIONS
+            .iter()
+            .filter(|direction| field.has_passage(direction))
+            .map(|direction| {
+                let (x, y) = offset(direction);
+                Coordinates::new(coordinates.x + x, coordinates.y + y)
+            })
+            .filter(|neighbour| self.contains(*neighbour))
+            .collect()
+    }
+
+    /// Shortest path between two cells, both included. It is empty if `to`
+    /// can't be reached from `from`.
+    pub(crate) fn path(&self, from: Coordinates, to: Coordinates) -> Vec<Coordinates> {
+        // Breadth first search, keeping track of where we came from
+        // to rebuild the path.
+        let mut previous = HashMap::from([(from, from)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = vec![to];
+                let mut cell = to;
+                while cell != from {
+                    cell = previous[&cell];
+                    path.push(cell);
+                }
+                path.reverse();
+                return path;
+            }
+
+            for neighbour in self.neighbours(current) {
+                if let Entry::Vacant(entry) = previous.entry(neighbour) {
+                    entry.insert(current);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        Vec::new()
+    }
+
+    /// Cells that can be reached from `from` without going through `blocked`
+    /// cells nor `closed` passages.
+    pub(crate) fn reachable(
+        &self,
+        from: Coordinates,
+        blocked: &HashSet<Coordinates>,
+        closed: &[(Coordinates, Coordinates)],
+    ) -> HashSet<Coordinates> {
+        let is_closed = |a: Coordinates, b: Coordinates| {
+            closed.iter().any(|(first, second)| {
+                (*first == a && *second == b) || (*first == b && *second == a)
+            })
+        };
+
+        let mut reached = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(current) = queue.pop_front() {
+            for neighbour in self.neighbours(current) {
+                if !blocked.contains(&neighbour)
+                    && !is_closed(current, neighbour)
+                    && reached.insert(neighbour)
+                {
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        reached
+    }
+
+    /// Shortest path from the start cell to the goal.
+    pub(crate) fn solution(&self) -> Vec<Coordinates> {
+        self.path(self.start, self.goal)
+    }
+}
+
+#[cfg(test)]
+impl CurrentMaze {
+    /// A default sized maze generated from `seed`, on a board of 5.
+    pub(crate) fn seeded(seed: u64) -> Self {
+        let mut config = MazeConfig::default();
+        config.set_seed(Some(seed));
+        let maze: Maze = (&config).try_into().unwrap();
+        Self::new(maze, config, 5.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cell_at_cell_center() {
+        let current_maze = CurrentMaze::seeded(42);
+        for cell in current_maze.cells() {
+            assert!(current_maze.cell_at(current_maze.cell_center(cell)) == Some(cell));
+        }
+    }
+
+    #[test]
+    fn cell_at_off_board() {
+        let current_maze = CurrentMaze::seeded(42);
+        assert!(current_maze.cell_at(Vec3::new(2.6, 0., 0.)).is_none());
+        assert!(current_maze.cell_at(Vec3::new(0., 0., -2.6)).is_none());
+    }
+
+    #[test]
+    fn fnv1a_reference_values() {
+        let mut hasher = Fnv1a::new();
+        assert_eq!(hasher.finish(), 0xcbf29ce484222325);
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn same_layout_same_hash() {
+        assert_eq!(
+            CurrentMaze::seeded(7).layout_hash,
+            CurrentMaze::seeded(7).layout_hash
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Results screen
+//!
+//! Shown when the goal is reached in free games, daily challenges and co-op
+//! runs, with the result of the run and the leaderboard, if any. The run can
+//! be watched again from there, see [crate::game::Replay]. Also shown when
+//! the game is over, the run not being saved then.
+
+use bevy::prelude::*;
+
+use crate::{
+    game::Replay,
+    options::GameOptions,
+    resources::{GameMode, MazeConfig},
+    AppState,
+};
+
+const TEXT_COLOR: Color = Color::rgb(0.1, 0.1, 0.1);
+const NORMAL_BUTTON: Color = Color::rgb(0.3, 0.3, 0.3);
+
+/// What the results screen shows, set before entering it.
+#[derive(Resource, Default)]
+pub(crate) struct Results {
+    title: String,
+    lines: Vec<String>,
+    /// Whether the run has been saved and can be watched.
+    replay: bool,
+}
+
+impl Results {
+    pub(crate) fn new(title: impl Into<String>, lines: Vec<String>) -> Self {
+        Self {
+            title: title.into(),
+            lines,
+            replay: true,
+        }
+    }
+
+    /// Results of a run that wasn't saved.
+    pub(crate) fn without_replay(mut self) -> Self {
+        self.replay = false;
+        self
+    }
+}
+
+#[derive(Component)]
+struct ResultsComponent;
+
+#[derive(Component, Clone, Copy)]
+enum ResultsAction {
+    /// Watch the run that just ended.
+    Replay,
+    Menu,
+}
+
+pub(crate) struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Results>()
+            .add_systems(OnEnter(AppState::Results), setup)
+            .add_systems(Update, handle_buttons.run_if(in_state(AppState::Results)))
+            .add_systems(OnExit(AppState::Results), remove::<ResultsComponent>);
+    }
+}
+
+fn setup(mut commands: Commands, results: Res<Results>) {
+    let text_style = TextStyle {
+        font_size: 30.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    commands
+        .spawn(Camera2dBundle::default())
+        .insert(ResultsComponent);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            ResultsComponent,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    results.title.clone(),
+                    TextStyle {
+                        font_size: 80.0,
+                        color: TEXT_COLOR,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(30.0)),
+                    ..default()
+                }),
+            );
+
+            for line in &results.lines {
+                parent.spawn(
+                    TextBundle::from_section(line.clone(), text_style.clone()).with_style(Style {
+                        margin: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    }),
+                );
+            }
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (action, label) in [
+                        (ResultsAction::Replay, "Watch replay"),
+                        (ResultsAction::Menu, "Menu"),
+                    ]
+                    .into_iter()
+                    .filter(|(action, _)| {
+                        results.replay || !matches!(action, ResultsAction::Replay)
+                    }) {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        width: Val::Px(250.0),
+                                        height: Val::Px(65.0),
+                                        margin: UiRect::all(Val::Px(10.0)),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: NORMAL_BUTTON.into(),
+                                    ..default()
+                                },
+                                action,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    label,
+                                    TextStyle {
+                                        font_size: 40.0,
+                                        color: TEXT_COLOR,
+                                        ..default()
+                                    },
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+#[allow(clippy::type_complexity)]
+fn handle_buttons(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &ResultsAction), (Changed<Interaction>, With<Button>)>,
+    mut state: ResMut<NextState<AppState>>,
+    mut mode: ResMut<GameMode>,
+    mut maze_config: ResMut<MazeConfig>,
+    mut options: GameOptions,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            match action {
+                ResultsAction::Replay => match Replay::load_saved() {
+                    Some(replay) => {
+                        replay.watch(&mut commands, &mut mode, &mut maze_config, &mut options);
+                        state.set(AppState::Game);
+                    }
+                    None => info!("No replay saved yet"),
+                },
+                ResultsAction::Menu => state.set(AppState::Menu),
+            }
+        }
+    }
+}
+
+fn remove<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
+    for entity in &to_despawn {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,69 @@
+//! Local storage
+//!
+//! Keep small pieces of data (progress, scores...) from one session to the
+//! next : in files of the `saves` directory on native and in the browser local
+//! storage with wasm. Values are stored as [RON](https://github.com/ron-rs/ron).
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Read the value saved under `key`, if any.
+pub(crate) fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let content = read(key)?;
+    match ron::from_str(&content) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Can't read saved {key} : {err}");
+            None
+        }
+    }
+}
+
+pub(crate) fn save<T: Serialize>(key: &str, value: &T) {
+    match ron::to_string(value) {
+        Ok(content) => write(key, &content),
+        Err(err) => warn!("Can't save {key} : {err}"),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DIRECTORY: &str = "saves";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> std::path::PathBuf {
+    std::path::Path::new(SAVE_DIRECTORY).join(format!("{key}.ron"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(path(key)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, content: &str) {
+    if let Err(err) =
+        std::fs::create_dir_all(SAVE_DIRECTORY).and_then(|_| std::fs::write(path(key), content))
+    {
+        warn!("Can't save {key} : {err}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, content: &str) {
+    let saved = local_storage()
+        .map(|storage| storage.set_item(key, content).is_ok())
+        .unwrap_or_default();
+    if !saved {
+        warn!("Can't save {key}");
+    }
+}